@@ -0,0 +1,72 @@
+-- 코디네이터가 쓰는 테이블. 마이그레이션 도입 전부터 쓰던 DB라면 이미 있으므로 그대로 둠
+
+CREATE TABLE IF NOT EXISTS `Submit` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `stud_id` int NOT NULL,
+  `type` char(1) DEFAULT NULL COMMENT '0 = 실행, 1 = 제출',
+  `problemNo` int NOT NULL,
+  `lang` varchar(10) DEFAULT NULL,
+  `code` text,
+  `state` char(1) DEFAULT NULL COMMENT '0 = 제출됨, 1 = 채점 중, 2 = 채점 완료',
+  `extra` varchar(255) DEFAULT NULL,
+  `result` char(1) DEFAULT NULL COMMENT '0 = 정답, 1 = 오답',
+  `code_size` int DEFAULT NULL,
+  `submit_at` datetime DEFAULT NULL,
+  `runtime` int DEFAULT NULL,
+  `memory` int DEFAULT NULL,
+  `score` int DEFAULT NULL,
+  `queued` tinyint NOT NULL DEFAULT '0' COMMENT '코디네이터가 가져감',
+  `needs_review` tinyint NOT NULL DEFAULT '0' COMMENT '교차 검증 결과가 갈림',
+  `claimed_by` varchar(64) DEFAULT NULL COMMENT '채점 중인 코디네이터',
+  `claim_expires_at` datetime DEFAULT NULL COMMENT '이 시각까지 갱신되지 않으면 다른 코디네이터가 가져갈 수 있음',
+  PRIMARY KEY (`id`),
+  KEY `Submit_FK` (`stud_id`) USING BTREE,
+  KEY `Submit_FK_1` (`problemNo`) USING BTREE,
+  KEY `Submit_queued` (`queued`, `type`, `id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci;
+
+CREATE TABLE IF NOT EXISTS `Testcase` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `input` text,
+  `output` text,
+  `problem_id` int NOT NULL,
+  `isPublic` tinyint NOT NULL DEFAULT '1',
+  `runtime` int DEFAULT NULL COMMENT 'ms 단위',
+  `memory_limit` int DEFAULT NULL,
+  `is_decimal_mode` int NOT NULL DEFAULT '0' COMMENT '실수 출력을 오차 범위 안에서 비교',
+  PRIMARY KEY (`id`),
+  KEY `Testcase_FK_1` (`problem_id`) USING BTREE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci;
+
+CREATE TABLE IF NOT EXISTS `Testcase_judge` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `submit_id` int NOT NULL,
+  `testcase_id` int NOT NULL,
+  `output` text,
+  `runtime` int DEFAULT NULL,
+  `result` tinyint DEFAULT NULL COMMENT '0 = 성공, 1 = 실패',
+  `compile_log` text,
+  `memory` int DEFAULT NULL,
+  `judge_at` datetime NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  `judge_server_id` varchar(64) NOT NULL DEFAULT '',
+  `result_extra` varchar(128) DEFAULT NULL,
+  `attempt` int NOT NULL DEFAULT '0' COMMENT '교차 검증시 몇 번째 실행인지',
+  PRIMARY KEY (`id`),
+  KEY `Testcase_judge_FK` (`submit_id`) USING BTREE,
+  KEY `Testcase_judge_testcase_FK` (`testcase_id`),
+  CONSTRAINT `Testcase_judge_submit_FK` FOREIGN KEY (`submit_id`) REFERENCES `Submit` (`id`) ON UPDATE CASCADE,
+  CONSTRAINT `Testcase_judge_testcase_FK` FOREIGN KEY (`testcase_id`) REFERENCES `Testcase` (`id`) ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci;
+
+CREATE TABLE IF NOT EXISTS `config` (
+  `key` varchar(64) NOT NULL,
+  `val` varchar(255) DEFAULT NULL COMMENT 'START_AT, END_AT은 %Y-%m-%d %H:%M:%S',
+  PRIMARY KEY (`key`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci;
+
+CREATE TABLE IF NOT EXISTS `user_problem_stat` (
+  `stud_id` int NOT NULL,
+  `problem_no` int NOT NULL,
+  `score` int DEFAULT NULL,
+  PRIMARY KEY (`stud_id`, `problem_no`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci;
@@ -0,0 +1,7 @@
+-- 같은 (제출, 테스트케이스, attempt) 결과는 한 행만 둠. 재채점이나 재시도로 쌓인 중복은 마지막 것만 남김
+
+DELETE a FROM `Testcase_judge` a
+  JOIN `Testcase_judge` b
+    ON a.`submit_id` = b.`submit_id` AND a.`testcase_id` = b.`testcase_id` AND a.`attempt` = b.`attempt` AND a.`id` < b.`id`;
+
+ALTER TABLE `Testcase_judge` ADD UNIQUE KEY `Testcase_judge_attempt` (`submit_id`, `testcase_id`, `attempt`);
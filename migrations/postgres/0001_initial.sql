@@ -0,0 +1,74 @@
+-- 코디네이터가 쓰는 테이블. MySQL과 이름을 맞추기 위해 대소문자가 섞인 이름은 따옴표로 감쌈
+
+CREATE TABLE IF NOT EXISTS "Submit" (
+    id SERIAL PRIMARY KEY,
+    stud_id INTEGER NOT NULL,
+    type SMALLINT, -- 0 = 실행, 1 = 제출
+    "problemNo" INTEGER NOT NULL,
+    lang VARCHAR(10),
+    code TEXT,
+    state SMALLINT NOT NULL DEFAULT 0, -- 0 = 제출됨, 1 = 채점 중, 2 = 채점 완료
+    extra VARCHAR(255),
+    result SMALLINT, -- 0 = 정답, 1 = 오답
+    code_size INTEGER,
+    submit_at TIMESTAMP,
+    runtime INTEGER,
+    memory INTEGER,
+    score INTEGER,
+    queued SMALLINT NOT NULL DEFAULT 0,
+    needs_review SMALLINT NOT NULL DEFAULT 0,
+    claimed_by VARCHAR(64),
+    claim_expires_at TIMESTAMPTZ
+);
+CREATE INDEX IF NOT EXISTS "Submit_queued" ON "Submit" (queued, type, id);
+CREATE INDEX IF NOT EXISTS "Submit_stud_problem" ON "Submit" (stud_id, "problemNo");
+
+CREATE TABLE IF NOT EXISTS "Testcase" (
+    id SERIAL PRIMARY KEY,
+    input TEXT,
+    output TEXT,
+    problem_id INTEGER NOT NULL,
+    "isPublic" BOOLEAN NOT NULL DEFAULT TRUE,
+    runtime INTEGER, -- ms 단위
+    memory_limit INTEGER,
+    is_decimal_mode INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS "Testcase_problem" ON "Testcase" (problem_id);
+
+CREATE TABLE IF NOT EXISTS "Testcase_judge" (
+    id SERIAL PRIMARY KEY,
+    submit_id INTEGER NOT NULL REFERENCES "Submit" (id),
+    testcase_id INTEGER NOT NULL REFERENCES "Testcase" (id),
+    output TEXT,
+    runtime INTEGER,
+    result SMALLINT, -- 0 = 성공, 1 = 실패
+    compile_log TEXT,
+    memory INTEGER,
+    judge_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    judge_server_id VARCHAR(64) NOT NULL DEFAULT '',
+    result_extra VARCHAR(128),
+    attempt INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS "Testcase_judge_submit" ON "Testcase_judge" (submit_id);
+
+CREATE TABLE IF NOT EXISTS config (
+    key VARCHAR(64) PRIMARY KEY,
+    val TEXT
+);
+
+CREATE TABLE IF NOT EXISTS user_problem_stat (
+    stud_id INTEGER NOT NULL,
+    problem_no INTEGER NOT NULL,
+    score INTEGER,
+    PRIMARY KEY (stud_id, problem_no)
+);
+
+CREATE OR REPLACE FUNCTION judge_notify_submit() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('judge_submit', NEW.id::text);
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql;
+DROP TRIGGER IF EXISTS "Submit_notify" ON "Submit";
+CREATE TRIGGER "Submit_notify" AFTER INSERT ON "Submit"
+    FOR EACH ROW EXECUTE FUNCTION judge_notify_submit();
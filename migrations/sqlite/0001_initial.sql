@@ -0,0 +1,64 @@
+-- 코디네이터가 쓰는 테이블. MySQL과 컬럼 이름은 같고, 코드로 저장하는 컬럼(`type`, `state`, `result`)은 정수로 둠
+
+CREATE TABLE IF NOT EXISTS Submit (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    stud_id INTEGER NOT NULL,
+    type INTEGER, -- 0 = 실행, 1 = 제출
+    problemNo INTEGER NOT NULL,
+    lang TEXT,
+    code TEXT,
+    state INTEGER NOT NULL DEFAULT 0, -- 0 = 제출됨, 1 = 채점 중, 2 = 채점 완료
+    extra TEXT,
+    result INTEGER, -- 0 = 정답, 1 = 오답
+    code_size INTEGER,
+    submit_at TEXT, -- %Y-%m-%d %H:%M:%S
+    runtime INTEGER,
+    memory INTEGER,
+    score INTEGER,
+    queued INTEGER NOT NULL DEFAULT 0,
+    needs_review INTEGER NOT NULL DEFAULT 0,
+    claimed_by TEXT,
+    claim_expires_at TEXT -- UTC
+);
+CREATE INDEX IF NOT EXISTS Submit_queued ON Submit (queued, type, id);
+CREATE INDEX IF NOT EXISTS Submit_stud_problem ON Submit (stud_id, problemNo);
+
+CREATE TABLE IF NOT EXISTS Testcase (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    input TEXT,
+    output TEXT,
+    problem_id INTEGER NOT NULL,
+    isPublic INTEGER NOT NULL DEFAULT 1,
+    runtime INTEGER, -- ms 단위
+    memory_limit INTEGER,
+    is_decimal_mode INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS Testcase_problem ON Testcase (problem_id);
+
+CREATE TABLE IF NOT EXISTS Testcase_judge (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    submit_id INTEGER NOT NULL REFERENCES Submit (id),
+    testcase_id INTEGER NOT NULL REFERENCES Testcase (id),
+    output TEXT,
+    runtime INTEGER,
+    result INTEGER, -- 0 = 성공, 1 = 실패
+    compile_log TEXT,
+    memory INTEGER,
+    judge_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    judge_server_id TEXT NOT NULL DEFAULT '',
+    result_extra TEXT,
+    attempt INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS Testcase_judge_submit ON Testcase_judge (submit_id);
+
+CREATE TABLE IF NOT EXISTS config (
+    key TEXT PRIMARY KEY,
+    val TEXT
+);
+
+CREATE TABLE IF NOT EXISTS user_problem_stat (
+    stud_id INTEGER NOT NULL,
+    problem_no INTEGER NOT NULL,
+    score INTEGER,
+    PRIMARY KEY (stud_id, problem_no)
+);
@@ -0,0 +1,6 @@
+-- 같은 (제출, 테스트케이스, attempt) 결과는 한 행만 둠. 재채점이나 재시도로 쌓인 중복은 마지막 것만 남김
+
+DELETE FROM Testcase_judge
+WHERE id NOT IN (SELECT MAX(id) FROM Testcase_judge GROUP BY submit_id, testcase_id, attempt);
+
+CREATE UNIQUE INDEX Testcase_judge_attempt ON Testcase_judge (submit_id, testcase_id, attempt);
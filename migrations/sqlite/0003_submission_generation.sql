@@ -0,0 +1,6 @@
+-- 채점을 새로 시작할 때마다 (관리자 재채점) 올라가는 번호. 테스트케이스 결과에도 남겨서
+-- 재채점 전의 결과를 이어서 쓰거나, 중단된 채점의 결과가 새 채점에 섞이지 않도록 함
+
+ALTER TABLE Submit ADD COLUMN generation INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE Testcase_judge ADD COLUMN generation INTEGER NOT NULL DEFAULT 0; -- 결과를 낸 채점의 Submit.generation
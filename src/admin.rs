@@ -0,0 +1,226 @@
+use std::time::Instant;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Serialize;
+use tokio::sync::oneshot;
+
+use crate::{
+    config, metrics,
+    queue::{PriorityClass, QueuedTask},
+    ChannelMessage, TX,
+};
+
+/// 관리자 API -> 매니저. 상태는 매니저만 들고 있으므로 요청마다 응답 채널을 같이 보냄
+#[derive(Debug)]
+pub enum AdminRequest {
+    Workers(oneshot::Sender<Vec<WorkerView>>),
+    Queues(oneshot::Sender<QueuesView>),
+    Submissions(oneshot::Sender<Vec<SubmissionView>>),
+    /// 제출 전체를 다시 채점함. 실패하면 사유
+    Rejudge(i32, oneshot::Sender<Result<(), String>>),
+    /// 채점 중인 제출을 중단함. 채점 중인 제출이 아니면 `false`
+    Cancel(i32, oneshot::Sender<bool>),
+    /// 워커를 내보냄. 없는 워커면 `false`
+    Drain(usize, oneshot::Sender<bool>),
+    /// 새 제출을 가져오지 않음 (`true`) / 다시 가져옴 (`false`)
+    PauseIntake(bool, oneshot::Sender<IntakeView>),
+    Intake(oneshot::Sender<IntakeView>),
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkerView {
+    pub channel_id: usize,
+    pub is_precise: bool,
+    pub is_working: bool,
+    pub is_draining: bool,
+    pub speed_factor: f64,
+    pub calibrated_secs_ago: Option<u64>,
+    pub current_task: Option<TaskView>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TaskView {
+    pub submission_id: i32,
+    pub testcase_id: i32,
+    pub stud_id: i32,
+    pub class: PriorityClass,
+    pub attempt: u32,
+    pub waited_ms: u128,
+    /// 큐에서 최대 대기 시간을 넘겼는지. 워커에 배정된 작업은 `false`
+    pub is_overdue: bool,
+}
+impl TaskView {
+    /// `now`: 스케줄러 시계 기준 현재 시각
+    pub fn new(task: &QueuedTask, is_overdue: bool, now: Instant) -> Self {
+        Self {
+            submission_id: task.submission.id,
+            testcase_id: task.testcase.id,
+            stud_id: task.submission.stud_id,
+            class: task.class,
+            attempt: task.attempt,
+            waited_ms: now.saturating_duration_since(task.enqueued_at).as_millis(),
+            is_overdue,
+        }
+    }
+}
+
+/// 큐에 있는 순서 그대로 (aging은 반영되지 않음)
+#[derive(Debug, Serialize)]
+pub struct QueuesView {
+    pub precise: Vec<TaskView>,
+    pub quick: Vec<TaskView>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubmissionView {
+    pub submission_id: i32,
+    pub stud_id: i32,
+    pub problem_no: i32,
+    pub class: PriorityClass,
+    pub state: String,
+    pub testcases_total: usize,
+    pub testcases_done: usize,
+    pub is_verified: bool,
+    pub needs_review: bool,
+    pub requeues: Vec<RequeueView>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RequeueView {
+    pub testcase_id: i32,
+    pub attempt: u32,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IntakeView {
+    pub is_paused: bool,
+}
+
+/// localhost에서만 받는 관리자 HTTP/JSON API
+///
+/// - `GET /workers`: 연결된 워커
+/// - `POST /workers/{channel_id}/drain`: 현재 테스트케이스까지만 채점하고 연결을 끊음
+/// - `GET /queues`: `task_precise` / `task_quick` 큐
+/// - `GET /submissions`: 채점 중인 제출
+/// - `POST /submissions/{id}/rejudge`: 제출 전체를 다시 채점
+/// - `POST /submissions/{id}/cancel`: 채점 중인 제출을 중단
+/// - `GET /intake`, `POST /intake/pause`, `POST /intake/resume`: 새 제출 가져오기를 멈추거나 다시 시작
+/// - `GET /metrics`: Prometheus 지표
+pub struct Admin {
+    tx_manager: TX,
+}
+impl Admin {
+    pub fn new(tx: TX) -> Self {
+        Admin { tx_manager: tx }
+    }
+
+    pub async fn run(self) {
+        let bind = &config::get().admin.bind;
+        let tcp_listener = match tokio::net::TcpListener::bind(bind).await {
+            Ok(tcp_listener) => tcp_listener,
+            Err(e) => {
+                tracing::error!(bind, error = %e, "admin api: cannot bind");
+                return;
+            }
+        };
+        tracing::info!(bind, "admin api listening");
+
+        let router = Router::new()
+            .route("/workers", get(workers))
+            .route("/workers/{channel_id}/drain", post(drain))
+            .route("/queues", get(queues))
+            .route("/submissions", get(submissions))
+            .route("/submissions/{id}/rejudge", post(rejudge))
+            .route("/submissions/{id}/cancel", post(cancel))
+            .route("/intake", get(intake))
+            .route("/intake/pause", post(pause_intake))
+            .route("/intake/resume", post(resume_intake))
+            .merge(metrics::router())
+            .with_state(self.tx_manager);
+
+        if let Err(e) = axum::serve(tcp_listener, router).await {
+            tracing::error!(error = %e, "admin api stopped");
+        }
+    }
+}
+
+/// 매니저에게 요청을 보내고 응답을 기다림. 매니저가 종료됐으면 503
+async fn request<T>(
+    tx: &TX,
+    make: impl FnOnce(oneshot::Sender<T>) -> AdminRequest,
+) -> Result<T, Response> {
+    let (reply_tx, reply_rx) = oneshot::channel();
+
+    let unavailable = || error(StatusCode::SERVICE_UNAVAILABLE, "manager unavailable");
+    tx.send(ChannelMessage::Admin(make(reply_tx)))
+        .await
+        .map_err(|_| unavailable())?;
+    reply_rx.await.map_err(|_| unavailable())
+}
+
+fn error(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, Json(serde_json::json!({ "error": message.into() }))).into_response()
+}
+
+fn ok() -> Response {
+    Json(serde_json::json!({ "ok": true })).into_response()
+}
+
+async fn workers(State(tx): State<TX>) -> Result<Json<Vec<WorkerView>>, Response> {
+    request(&tx, AdminRequest::Workers).await.map(Json)
+}
+
+async fn drain(State(tx): State<TX>, Path(channel_id): Path<usize>) -> Result<Response, Response> {
+    match request(&tx, |reply| AdminRequest::Drain(channel_id, reply)).await? {
+        true => Ok(ok()),
+        false => Err(error(StatusCode::NOT_FOUND, "no such worker")),
+    }
+}
+
+async fn queues(State(tx): State<TX>) -> Result<Json<QueuesView>, Response> {
+    request(&tx, AdminRequest::Queues).await.map(Json)
+}
+
+async fn submissions(State(tx): State<TX>) -> Result<Json<Vec<SubmissionView>>, Response> {
+    request(&tx, AdminRequest::Submissions).await.map(Json)
+}
+
+async fn rejudge(State(tx): State<TX>, Path(id): Path<i32>) -> Result<Response, Response> {
+    match request(&tx, |reply| AdminRequest::Rejudge(id, reply)).await? {
+        Ok(()) => Ok(ok()),
+        Err(reason) => Err(error(StatusCode::CONFLICT, reason)),
+    }
+}
+
+async fn cancel(State(tx): State<TX>, Path(id): Path<i32>) -> Result<Response, Response> {
+    match request(&tx, |reply| AdminRequest::Cancel(id, reply)).await? {
+        true => Ok(ok()),
+        false => Err(error(
+            StatusCode::NOT_FOUND,
+            "submission is not being judged",
+        )),
+    }
+}
+
+async fn intake(State(tx): State<TX>) -> Result<Json<IntakeView>, Response> {
+    request(&tx, AdminRequest::Intake).await.map(Json)
+}
+
+async fn pause_intake(State(tx): State<TX>) -> Result<Json<IntakeView>, Response> {
+    request(&tx, |reply| AdminRequest::PauseIntake(true, reply))
+        .await
+        .map(Json)
+}
+
+async fn resume_intake(State(tx): State<TX>) -> Result<Json<IntakeView>, Response> {
+    request(&tx, |reply| AdminRequest::PauseIntake(false, reply))
+        .await
+        .map(Json)
+}
@@ -0,0 +1,573 @@
+//! 가짜 워커 여러 대를 띄워서 코디네이터에 부하를 주는 도구
+//!
+//! 실제 샌드박스 없이 스케줄러를 시험하기 위한 것. 워커마다 코디네이터에 접속해서 등록하고,
+//! 받은 작업은 ack한 뒤 정해진 분포대로 기다렸다가 판정을 돌려줌.
+//! 끝나면 처리량과 코디네이터의 `/metrics`에서 읽은 큐 대기 시간 백분위를 출력함
+//!
+//! ```text
+//! cargo run --bin fleet -- --precise 8 --quick 4 --duration 60 \
+//!     --latency-ms 50-500 --verdicts accepted=90,wrong_answer=8,runtime_error=2 \
+//!     --disconnect-rate 0.01 --hang-rate 0.005
+//! ```
+//!
+//! 속도 측정 작업(`calibration.enabled`)에는 분포와 상관없이 항상 정답과 `--benchmark-ms`로 답하므로
+//! 모든 워커의 `speed_factor`가 같게 나옴
+
+#[allow(dead_code)]
+#[path = "../calibration.rs"]
+mod calibration;
+#[allow(dead_code)]
+#[path = "../protocol.rs"]
+mod protocol;
+#[allow(dead_code)]
+#[path = "../types.rs"]
+mod types;
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use bytes::BytesMut;
+use rand::Rng;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use protocol::*;
+use types::*;
+
+const USAGE: &str = "usage: fleet [--addr HOST:PORT] [--precise N] [--quick N] [--duration SECS]
+             [--latency-ms MIN-MAX] [--verdicts NAME=WEIGHT,..] [--disconnect-rate P]
+             [--hang-rate P] [--reconnect-ms MIN-MAX] [--benchmark-ms MS] [--metrics-url URL]
+
+verdicts: accepted, wrong_answer, time_limit_exceeded, memory_limit_exceeded,
+          output_limit_exceeded, compile_failed, runtime_error";
+
+#[derive(Debug, Clone)]
+struct Options {
+    addr: String,
+    precise: usize,
+    quick: usize,
+    duration: Duration,
+    /// 작업 하나를 처리하는 데 걸리는 시간 (균등 분포)
+    latency_ms: (u64, u64),
+    /// (판정, 가중치)
+    verdicts: Vec<(TestCaseJudgeResultInner, u32)>,
+    /// 작업을 받은 뒤 결과 없이 연결을 끊을 확률
+    disconnect_rate: f64,
+    /// 작업을 받은 뒤 아무 응답도 하지 않을 확률 (코디네이터의 watchdog 시험용)
+    hang_rate: f64,
+    /// 연결이 끊긴 뒤 다시 접속할 때까지 기다리는 시간
+    reconnect_ms: (u64, u64),
+    /// 속도 측정 작업에 걸렸다고 답하는 시간. `calibration.reference_ms`와 같으면 `speed_factor`가 1
+    benchmark_ms: u64,
+    /// 비워두면 큐 대기 시간을 출력하지 않음
+    metrics_url: String,
+}
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            addr: "127.0.0.1:33333".to_string(),
+            precise: 4,
+            quick: 4,
+            duration: Duration::from_secs(60),
+            latency_ms: (50, 500),
+            verdicts: vec![(TestCaseJudgeResultInner::Accepted, 1)],
+            disconnect_rate: 0.0,
+            hang_rate: 0.0,
+            reconnect_ms: (500, 2000),
+            benchmark_ms: 1000,
+            metrics_url: "http://127.0.0.1:33334/metrics".to_string(),
+        }
+    }
+}
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options::default();
+
+        while let Some(key) = args.next() {
+            let mut value = || args.next().ok_or(format!("missing value for {}", key));
+
+            match key.as_str() {
+                "--addr" => options.addr = value()?,
+                "--precise" => options.precise = parse_num(&value()?)?,
+                "--quick" => options.quick = parse_num(&value()?)?,
+                "--duration" => options.duration = Duration::from_secs(parse_num(&value()?)?),
+                "--latency-ms" => options.latency_ms = parse_range(&value()?)?,
+                "--verdicts" => options.verdicts = parse_verdicts(&value()?)?,
+                "--disconnect-rate" => options.disconnect_rate = parse_rate(&value()?)?,
+                "--hang-rate" => options.hang_rate = parse_rate(&value()?)?,
+                "--reconnect-ms" => options.reconnect_ms = parse_range(&value()?)?,
+                "--benchmark-ms" => options.benchmark_ms = parse_num(&value()?)?,
+                "--metrics-url" => options.metrics_url = value()?,
+                _ => return Err(format!("unknown option {}", key)),
+            }
+        }
+
+        if options.precise + options.quick == 0 {
+            return Err("at least one worker is required".to_string());
+        }
+        Ok(options)
+    }
+}
+
+fn parse_num<T: std::str::FromStr>(s: &str) -> Result<T, String> {
+    s.parse().map_err(|_| format!("invalid number {:?}", s))
+}
+
+fn parse_rate(s: &str) -> Result<f64, String> {
+    match parse_num::<f64>(s)? {
+        rate if (0.0..=1.0).contains(&rate) => Ok(rate),
+        _ => Err(format!("rate must be between 0 and 1: {:?}", s)),
+    }
+}
+
+/// `MIN-MAX` 또는 `N`
+fn parse_range(s: &str) -> Result<(u64, u64), String> {
+    let (min, max) = match s.split_once('-') {
+        Some((min, max)) => (parse_num(min)?, parse_num(max)?),
+        None => {
+            let n = parse_num(s)?;
+            (n, n)
+        }
+    };
+
+    if min > max {
+        return Err(format!("invalid range {:?}", s));
+    }
+    Ok((min, max))
+}
+
+fn parse_verdicts(s: &str) -> Result<Vec<(TestCaseJudgeResultInner, u32)>, String> {
+    let verdicts = s
+        .split(',')
+        .map(|item| {
+            let (name, weight) = item
+                .split_once('=')
+                .ok_or(format!("expected NAME=WEIGHT: {:?}", item))?;
+            let verdict = match name {
+                "accepted" => TestCaseJudgeResultInner::Accepted,
+                "wrong_answer" => TestCaseJudgeResultInner::WrongAnswer,
+                "time_limit_exceeded" => TestCaseJudgeResultInner::TimeLimitExceeded,
+                "memory_limit_exceeded" => TestCaseJudgeResultInner::MemoryLimitExceeded,
+                "output_limit_exceeded" => TestCaseJudgeResultInner::OutputLimitExceeded,
+                "compile_failed" => TestCaseJudgeResultInner::CompileFailed,
+                "runtime_error" => TestCaseJudgeResultInner::RuntimeError,
+                _ => return Err(format!("unknown verdict {:?}", name)),
+            };
+
+            Ok((verdict, parse_num(weight)?))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if verdicts.iter().all(|(_, weight)| *weight == 0) {
+        return Err("at least one verdict must have a positive weight".to_string());
+    }
+    Ok(verdicts)
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    tasks: AtomicU64,
+    results: AtomicU64,
+    disconnects: AtomicU64,
+    hangs: AtomicU64,
+    drained: AtomicU64,
+    verdicts: Mutex<BTreeMap<String, u64>>,
+    /// 결과를 보낸 뒤 다음 작업을 받을 때까지 기다린 시간 (ms)
+    idle_ms: Mutex<Vec<u64>>,
+}
+
+fn main() {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    rt.block_on(run(options));
+}
+
+async fn run(options: Options) {
+    let options = Arc::new(options);
+    let stats = Arc::new(Stats::default());
+    let started_at = Instant::now();
+
+    println!(
+        "starting {} precise + {} quick fake workers against {} for {}s",
+        options.precise,
+        options.quick,
+        options.addr,
+        options.duration.as_secs()
+    );
+
+    for worker_id in 0..options.precise + options.quick {
+        let is_precise = worker_id < options.precise;
+        tokio::spawn(worker(
+            worker_id,
+            is_precise,
+            options.clone(),
+            stats.clone(),
+        ));
+    }
+
+    let mut progress = tokio::time::interval(Duration::from_secs(5));
+    progress.tick().await;
+    let deadline = tokio::time::sleep(options.duration);
+    tokio::pin!(deadline);
+
+    loop {
+        tokio::select! {
+            _ = &mut deadline => break,
+            _ = progress.tick() => {
+                println!(
+                    "[{:>4}s] tasks {} results {} disconnects {} hangs {}",
+                    started_at.elapsed().as_secs(),
+                    stats.tasks.load(Ordering::Relaxed),
+                    stats.results.load(Ordering::Relaxed),
+                    stats.disconnects.load(Ordering::Relaxed),
+                    stats.hangs.load(Ordering::Relaxed),
+                );
+            }
+        }
+    }
+
+    report(&options, &stats, started_at.elapsed()).await;
+}
+
+/// 끊기면 다시 접속함. 코디네이터가 내보내면 (drain) 종료
+async fn worker(worker_id: usize, is_precise: bool, options: Arc<Options>, stats: Arc<Stats>) {
+    loop {
+        match FakeWorker::connect(worker_id, is_precise, &options, &stats).await {
+            Some(mut fake) => {
+                if fake.run().await.is_ok() {
+                    stats.drained.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+            }
+            None => eprintln!("worker {}: cannot connect to {}", worker_id, options.addr),
+        }
+
+        let delay = rand::thread_rng().gen_range(options.reconnect_ms.0..=options.reconnect_ms.1);
+        tokio::time::sleep(Duration::from_millis(delay)).await;
+    }
+}
+
+struct FakeWorker<'a> {
+    worker_id: usize,
+    stream: TcpStream,
+    recv_buf: BytesMut,
+    send_buf: BytesMut,
+
+    options: &'a Options,
+    stats: &'a Stats,
+}
+impl<'a> FakeWorker<'a> {
+    async fn connect(
+        worker_id: usize,
+        is_precise: bool,
+        options: &'a Options,
+        stats: &'a Stats,
+    ) -> Option<Self> {
+        let stream = TcpStream::connect(&options.addr).await.ok()?;
+
+        let mut fake = FakeWorker {
+            worker_id,
+            stream,
+            recv_buf: BytesMut::with_capacity(8192),
+            send_buf: BytesMut::with_capacity(8192),
+            options,
+            stats,
+        };
+        fake.send(Message::Register(MsgRegister {
+            is_precise_server: is_precise,
+        }))
+        .await
+        .ok()?;
+
+        Some(fake)
+    }
+
+    /// 코디네이터가 종료하라고 하면 `Ok`, 연결이 끊기면 `Err`
+    async fn run(&mut self) -> Result<(), ()> {
+        let mut idle_since = Instant::now();
+
+        loop {
+            let task = match self.recv().await? {
+                Message::SetTask(task) => task,
+                Message::Shutdown(_) => return Ok(()),
+                Message::Reset(_) => return Err(()),
+                _ => continue,
+            };
+
+            // 속도 측정은 부하 시험 대상이 아니므로 바로 정해진 결과를 돌려줌
+            if task.submission_id == calibration::BENCHMARK_ID {
+                self.send(Message::SetTaskAck(MsgSetTaskAck {
+                    submission_id: task.submission_id,
+                    testcase_id: task.testcase_id,
+                }))
+                .await?;
+                self.send(result_message(
+                    &task,
+                    TestCaseJudgeResultInner::Accepted,
+                    self.options.benchmark_ms,
+                    self.worker_id,
+                ))
+                .await?;
+                continue;
+            }
+
+            self.stats.tasks.fetch_add(1, Ordering::Relaxed);
+            self.stats
+                .idle_ms
+                .lock()
+                .unwrap()
+                .push(idle_since.elapsed().as_millis() as u64);
+
+            self.send(Message::SetTaskAck(MsgSetTaskAck {
+                submission_id: task.submission_id,
+                testcase_id: task.testcase_id,
+            }))
+            .await?;
+
+            let (latency, roll, verdict) = {
+                let mut rng = rand::thread_rng();
+                let latency =
+                    rng.gen_range(self.options.latency_ms.0..=self.options.latency_ms.1);
+                (latency, rng.gen::<f64>(), self.pick_verdict(&mut rng))
+            };
+            tokio::time::sleep(Duration::from_millis(latency)).await;
+
+            if roll < self.options.disconnect_rate {
+                self.stats.disconnects.fetch_add(1, Ordering::Relaxed);
+                return Err(());
+            }
+            if roll < self.options.disconnect_rate + self.options.hang_rate {
+                // 코디네이터가 watchdog으로 연결을 끊을 때까지 아무것도 하지 않음
+                self.stats.hangs.fetch_add(1, Ordering::Relaxed);
+                while self.recv().await.is_ok() {}
+                return Err(());
+            }
+
+            self.send(result_message(&task, verdict.clone(), latency, self.worker_id))
+                .await?;
+            self.stats.results.fetch_add(1, Ordering::Relaxed);
+            *self
+                .stats
+                .verdicts
+                .lock()
+                .unwrap()
+                .entry(format!("{:?}", verdict))
+                .or_default() += 1;
+
+            idle_since = Instant::now();
+        }
+    }
+
+    fn pick_verdict(&self, rng: &mut impl Rng) -> TestCaseJudgeResultInner {
+        let total: u32 = self.options.verdicts.iter().map(|(_, weight)| weight).sum();
+        let mut pick = rng.gen_range(0..total);
+
+        for (verdict, weight) in &self.options.verdicts {
+            if pick < *weight {
+                return verdict.clone();
+            }
+            pick -= weight;
+        }
+        unreachable!()
+    }
+
+    async fn send(&mut self, msg: Message) -> Result<(), ()> {
+        let body: MessageBody = msg.into();
+        body.encode(&mut self.send_buf);
+
+        self.stream
+            .write_all_buf(&mut self.send_buf)
+            .await
+            .map_err(|_| ())
+    }
+
+    async fn recv(&mut self) -> Result<Message, ()> {
+        loop {
+            if let Some(msg) = MessageBody::decode_buf(&mut self.recv_buf) {
+                return msg.try_into();
+            }
+
+            match self.stream.read_buf(&mut self.recv_buf).await {
+                Ok(0) | Err(_) => return Err(()),
+                Ok(_) => (),
+            }
+        }
+    }
+}
+
+fn result_message(
+    task: &MsgSetTask,
+    verdict: TestCaseJudgeResultInner,
+    latency: u64,
+    worker_id: usize,
+) -> Message {
+    let is_accepted = matches!(verdict, TestCaseJudgeResultInner::Accepted);
+    let time_used = match verdict {
+        TestCaseJudgeResultInner::TimeLimitExceeded => task.time_limit,
+        _ => latency.min(task.time_limit),
+    };
+
+    let result = MsgResult {
+        submission_id: task.submission_id,
+        testcase_id: task.testcase_id,
+
+        output_compile: String::new(),
+        output_run: if is_accepted {
+            task.expect_output.clone()
+        } else {
+            String::new()
+        },
+        result: verdict,
+        result_extra: String::new(),
+
+        time_used,
+        memory_used: 1024 * 1024,
+        judge_server_id: format!("fleet-{}", worker_id),
+    };
+
+    match is_accepted {
+        true => Message::ResultSuccess(MsgResultSuccess(result)),
+        false => Message::ResultFailed(MsgResultFailed(result)),
+    }
+}
+
+async fn report(options: &Options, stats: &Stats, elapsed: Duration) {
+    let results = stats.results.load(Ordering::Relaxed);
+
+    println!();
+    println!("=== fleet report ({:.1}s) ===", elapsed.as_secs_f64());
+    println!("tasks received    {}", stats.tasks.load(Ordering::Relaxed));
+    println!("results sent      {}", results);
+    println!(
+        "throughput        {:.2} results/s",
+        results as f64 / elapsed.as_secs_f64()
+    );
+    println!("disconnects       {}", stats.disconnects.load(Ordering::Relaxed));
+    println!("hangs             {}", stats.hangs.load(Ordering::Relaxed));
+    println!("drained workers   {}", stats.drained.load(Ordering::Relaxed));
+
+    println!("verdicts");
+    for (verdict, count) in stats.verdicts.lock().unwrap().iter() {
+        println!("  {:<22}{}", verdict, count);
+    }
+
+    let mut idle = stats.idle_ms.lock().unwrap().clone();
+    idle.sort_unstable();
+    println!(
+        "worker idle (ms)  p50 {} p90 {} p99 {}",
+        percentile(&idle, 0.5),
+        percentile(&idle, 0.9),
+        percentile(&idle, 0.99)
+    );
+
+    if options.metrics_url.is_empty() {
+        return;
+    }
+    match queue_wait(&options.metrics_url).await {
+        Ok(classes) if classes.is_empty() => println!("queue wait: no samples"),
+        Ok(classes) => {
+            println!("queue wait (s, from coordinator dispatch latency)");
+            for (class, buckets) in classes {
+                println!(
+                    "  {:<22}p50 {:.3} p90 {:.3} p99 {:.3} (n={})",
+                    class,
+                    histogram_quantile(&buckets, 0.5),
+                    histogram_quantile(&buckets, 0.9),
+                    histogram_quantile(&buckets, 0.99),
+                    buckets.last().map(|(_, count)| *count).unwrap_or(0.0)
+                );
+            }
+        }
+        Err(e) => println!("queue wait: cannot read {}: {}", options.metrics_url, e),
+    }
+}
+
+fn percentile(sorted: &[u64], q: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+
+    sorted[((sorted.len() - 1) as f64 * q).round() as usize]
+}
+
+/// class마다 (le, 누적 개수) 목록
+async fn queue_wait(metrics_url: &str) -> Result<BTreeMap<String, Vec<(f64, f64)>>, String> {
+    let text = reqwest::get(metrics_url)
+        .await
+        .map_err(|e| e.to_string())?
+        .text()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut classes: BTreeMap<String, Vec<(f64, f64)>> = BTreeMap::new();
+    for line in text.lines() {
+        let Some(rest) = line.strip_prefix("judge_dispatch_latency_seconds_bucket{") else {
+            continue;
+        };
+        let Some((labels, count)) = rest.split_once("} ") else {
+            continue;
+        };
+
+        let label = |name: &str| {
+            labels.split(',').find_map(|pair| {
+                let (key, value) = pair.split_once('=')?;
+                (key == name).then(|| value.trim_matches('"').to_string())
+            })
+        };
+        let (Some(class), Some(le), Ok(count)) = (label("class"), label("le"), count.parse())
+        else {
+            continue;
+        };
+        let le = if le == "+Inf" {
+            f64::INFINITY
+        } else {
+            le.parse().unwrap_or(f64::INFINITY)
+        };
+
+        classes.entry(class).or_default().push((le, count));
+    }
+
+    for buckets in classes.values_mut() {
+        buckets.sort_by(|a, b| a.0.total_cmp(&b.0));
+    }
+    classes.retain(|_, buckets| buckets.last().is_some_and(|(_, count)| *count > 0.0));
+
+    Ok(classes)
+}
+
+/// Prometheus의 histogram_quantile과 같은 방식 (버킷 안에서는 선형 보간)
+fn histogram_quantile(buckets: &[(f64, f64)], q: f64) -> f64 {
+    let Some(&(_, total)) = buckets.last() else {
+        return 0.0;
+    };
+    let rank = q * total;
+
+    let mut prev = (0.0, 0.0);
+    for &(le, count) in buckets {
+        if count >= rank {
+            if le.is_infinite() {
+                return prev.0;
+            }
+            if count == prev.1 {
+                return le;
+            }
+            return prev.0 + (le - prev.0) * (rank - prev.1) / (count - prev.1);
+        }
+        prev = (le, count);
+    }
+
+    prev.0
+}
@@ -0,0 +1,55 @@
+use crate::{protocol::*, types::*};
+
+/// 워커 속도 측정용 작업의 submission_id / testcase_id. 실제 제출과 겹치지 않도록 음수
+pub const BENCHMARK_ID: i64 = -1;
+
+/// 모든 워커에서 같은 결과가 나와야 하므로 입력 없이 정해진 계산만 함
+const BENCHMARK_CODE: &str = r#"#include <stdio.h>
+
+int main(void) {
+    unsigned long long x = 1, sum = 0;
+    for (long i = 0; i < 300000000L; i++) {
+        x = x * 6364136223846793005ULL + 1442695040888963407ULL;
+        sum ^= x >> 33;
+    }
+    printf("%llu\n", sum);
+    return 0;
+}
+"#;
+const BENCHMARK_OUTPUT: &str = "1800129597\n";
+
+/// 측정이 튀어도 시간 제한이 비정상적으로 바뀌지 않도록 제한함
+const SPEED_FACTOR_MIN: f64 = 0.25;
+const SPEED_FACTOR_MAX: f64 = 4.0;
+
+pub fn benchmark_task(time_limit: u64, memory_limit: u64) -> MsgSetTask {
+    MsgSetTask {
+        submission_id: BENCHMARK_ID,
+        testcase_id: BENCHMARK_ID,
+
+        lang: SubmissionLanguage::C,
+        code: BENCHMARK_CODE.to_string(),
+        input: String::new(),
+        expect_output: BENCHMARK_OUTPUT.to_string(),
+
+        time_limit,
+        memory_limit,
+
+        is_decimal_mode: false,
+    }
+}
+
+/// 기준 머신 대비 워커가 얼마나 느린지 (1보다 크면 느림)
+pub fn speed_factor(time_used: u64, reference_ms: u64) -> f64 {
+    (time_used as f64 / reference_ms as f64).clamp(SPEED_FACTOR_MIN, SPEED_FACTOR_MAX)
+}
+
+/// 기준 머신의 시간 제한을 이 워커에서의 시간 제한으로
+pub fn scale_time_limit(time_limit: u64, speed_factor: f64) -> u64 {
+    (time_limit as f64 * speed_factor).ceil() as u64
+}
+
+/// 이 워커에서 잰 실행 시간을 기준 머신의 실행 시간으로
+pub fn normalize_runtime(time_used: u64, speed_factor: f64) -> u64 {
+    (time_used as f64 / speed_factor).round() as u64
+}
@@ -0,0 +1,54 @@
+use std::fmt::Debug;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 스케줄러가 쓰는 시계. 큐 대기 시간, aging, 속도 재측정 주기 등은 모두 이 시계 기준
+///
+/// 시뮬레이션에서는 `VirtualClock`으로 바꿔서 시간을 직접 움직임
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+#[derive(Debug, Default)]
+pub struct SystemClock;
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// `advance`/`set`을 호출할 때만 움직이는 시계
+#[derive(Debug)]
+pub struct VirtualClock {
+    start: Instant,
+    elapsed: Mutex<Duration>,
+}
+impl VirtualClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            elapsed: Mutex::new(Duration::ZERO),
+        }
+    }
+
+    /// 시작한 뒤 흐른 (가상) 시간
+    pub fn elapsed(&self) -> Duration {
+        *self.elapsed.lock().unwrap()
+    }
+
+    /// 시간은 되돌아가지 않음
+    pub fn set(&self, elapsed: Duration) {
+        let mut current = self.elapsed.lock().unwrap();
+        *current = (*current).max(elapsed);
+    }
+}
+impl Default for VirtualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Clock for VirtualClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+}
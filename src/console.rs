@@ -0,0 +1,38 @@
+use tokio::io::AsyncBufReadExt;
+
+use crate::{ChannelMessage, TX};
+
+/// 표준 입력으로 받는 관리자 명령
+///
+/// - `drain <channel_id>`: 해당 워커는 현재 테스트케이스까지만 채점하고 연결을 끊음
+pub struct Console {
+    tx_manager: TX,
+}
+impl Console {
+    pub fn new(tx: TX) -> Self {
+        Console { tx_manager: tx }
+    }
+
+    pub async fn run(self) {
+        let mut lines = tokio::io::BufReader::new(tokio::io::stdin()).lines();
+
+        while let Ok(Some(line)) = lines.next_line().await {
+            let mut args = line.split_whitespace();
+
+            match (args.next(), args.next().map(str::parse::<usize>)) {
+                (None, _) => (),
+                (Some("drain"), Some(Ok(channel_id))) => {
+                    if self
+                        .tx_manager
+                        .send(ChannelMessage::Drain(channel_id))
+                        .await
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+                _ => eprintln!("usage: drain <channel_id>"),
+            }
+        }
+    }
+}
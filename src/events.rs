@@ -0,0 +1,150 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::get,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tokio_stream::{wrappers::BroadcastStream, StreamExt};
+
+use crate::config;
+
+/// 채점 진행 상황. 웹 프론트엔드가 `Submit` 테이블을 폴링하지 않도록 SSE로 내보냄
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JudgeEvent {
+    /// 코디네이터가 제출을 가져와서 큐에 넣음
+    Queued { submission_id: i32, stud_id: i32 },
+    /// 테스트케이스 하나의 판정이 확정됨 (교차 검증 중이면 두 결과를 비교한 뒤)
+    Testcase {
+        submission_id: i32,
+        stud_id: i32,
+        testcase_id: i32,
+        is_public: bool,
+        is_passed: bool,
+        verdict: String,
+        runtime: Option<usize>,
+        memory: Option<usize>,
+    },
+    /// 최종 판정. `Submit` 테이블에 기록된 값과 같음
+    Finished {
+        submission_id: i32,
+        stud_id: i32,
+        is_correct: bool,
+        verdict: String,
+        runtime: usize,
+        memory: usize,
+    },
+}
+impl JudgeEvent {
+    fn submission_id(&self) -> i32 {
+        match self {
+            Self::Queued { submission_id, .. }
+            | Self::Testcase { submission_id, .. }
+            | Self::Finished { submission_id, .. } => *submission_id,
+        }
+    }
+
+    fn stud_id(&self) -> i32 {
+        match self {
+            Self::Queued { stud_id, .. }
+            | Self::Testcase { stud_id, .. }
+            | Self::Finished { stud_id, .. } => *stud_id,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Queued { .. } => "queued",
+            Self::Testcase { .. } => "testcase",
+            Self::Finished { .. } => "finished",
+        }
+    }
+}
+
+pub type EventTX = broadcast::Sender<JudgeEvent>;
+
+/// 받는 쪽이 없어도 에러가 아니므로 결과는 무시함
+pub fn publish(tx: &EventTX, event: JudgeEvent) {
+    let _ = tx.send(event);
+}
+
+/// 둘 중 하나는 있어야 함. 둘 다 있으면 둘 다 맞는 이벤트만
+#[derive(Debug, Deserialize)]
+struct EventFilter {
+    submission_id: Option<i32>,
+    stud_id: Option<i32>,
+}
+impl EventFilter {
+    fn matches(&self, event: &JudgeEvent) -> bool {
+        self.submission_id
+            .is_none_or(|id| id == event.submission_id())
+            && self.stud_id.is_none_or(|id| id == event.stud_id())
+    }
+}
+
+/// 채점 이벤트 SSE 서버
+///
+/// - `GET /events?submission_id=..`: 제출 하나의 이벤트
+/// - `GET /events?stud_id=..`: 학생 한 명의 모든 제출의 이벤트
+///
+/// 연결한 뒤의 이벤트만 받으므로 프론트엔드는 연결한 다음 `Submit` 테이블을 한번 읽어야 함.
+/// 이벤트가 너무 밀리면 밀린 만큼 건너뜀
+pub struct Events {
+    tx_events: EventTX,
+}
+impl Events {
+    pub fn new(tx: EventTX) -> Self {
+        Events { tx_events: tx }
+    }
+
+    pub async fn run(self) {
+        let bind = &config::get().events.bind;
+        let tcp_listener = match tokio::net::TcpListener::bind(bind).await {
+            Ok(tcp_listener) => tcp_listener,
+            Err(e) => {
+                tracing::error!(bind, error = %e, "events: cannot bind");
+                return;
+            }
+        };
+        tracing::info!(bind, "events listening");
+
+        let router = Router::new()
+            .route("/events", get(events))
+            .with_state(self.tx_events);
+
+        if let Err(e) = axum::serve(tcp_listener, router).await {
+            tracing::error!(error = %e, "events stopped");
+        }
+    }
+}
+
+async fn events(State(tx): State<EventTX>, Query(filter): Query<EventFilter>) -> Response {
+    if filter.submission_id.is_none() && filter.stud_id.is_none() {
+        return (
+            StatusCode::BAD_REQUEST,
+            "submission_id or stud_id is required",
+        )
+            .into_response();
+    }
+
+    let stream = BroadcastStream::new(tx.subscribe()).filter_map(move |event| {
+        let event = event.ok().filter(|event| filter.matches(event))?;
+        let data = serde_json::to_string(&event).ok()?;
+
+        Some(Ok::<_, Infallible>(
+            Event::default().event(event.name()).data(data),
+        ))
+    });
+
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
@@ -1,8 +1,11 @@
+use std::time::{Duration, Instant};
+
 use bytes::*;
+use tracing::Instrument;
 
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
-use crate::{protocol::*, types::*, ChannelMessage, Submission, RX, TX};
+use crate::{calibration, config, protocol::*, types::*, ChannelMessage, Submission, RX, TX};
 
 pub struct Listener {
     tx_manager: TX,
@@ -13,7 +16,7 @@ impl Listener {
     }
 
     pub async fn run(self) {
-        let tcp_listener = tokio::net::TcpListener::bind("0.0.0.0:33333")
+        let tcp_listener = tokio::net::TcpListener::bind(&config::get().listener.bind)
             .await
             .unwrap();
 
@@ -40,8 +43,14 @@ pub struct Stream {
     current_submission: Option<Submission>,
     current_testcase: Option<TestCase>,
 
-    is_started: bool,
-    start_dt: Option<std::time::Instant>,
+    is_draining: bool,
+    is_calibrating: bool,
+    /// 이 시간까지 결과가 없으면 워커가 죽은거로 판단. 작업 중이 아니면 `None`
+    deadline: Option<Instant>,
+    watchdog_budget: Duration,
+
+    /// 기준 머신 대비 이 워커가 느린 정도. 시간 제한과 실행 시간을 이 값으로 보정함
+    speed_factor: f64,
 }
 impl Stream {
     pub fn new(tx_manager: TX, stream: tokio::net::TcpStream) -> Self {
@@ -56,8 +65,12 @@ impl Stream {
             current_submission: None,
             current_testcase: None,
 
-            is_started: false,
-            start_dt: None,
+            is_draining: false,
+            is_calibrating: false,
+            deadline: None,
+            watchdog_budget: Duration::ZERO,
+
+            speed_factor: 1.0,
         }
     }
 
@@ -82,7 +95,8 @@ impl Stream {
             self.stream_id = id;
         }
 
-        self.judge(stream_rx).await;
+        let span = tracing::info_span!("worker", channel_id = self.stream_id);
+        self.judge(stream_rx).instrument(span).await;
 
         // println!("listner loop end - {:?}", self);
 
@@ -106,9 +120,9 @@ impl Stream {
                     // eprintln!("recv on listener {:?}", msg);
                     match msg {
                         Some(ChannelMessage::WorkStart(submission, test_case)) => {
-                            if self.current_submission.is_some() {
+                            if self.current_submission.is_some() || self.is_draining || self.is_calibrating {
                                 self.tx_manager
-                                  .send(ChannelMessage::Refuse(submission, test_case))
+                                  .send(ChannelMessage::Refuse(self.stream_id, submission, test_case))
                                   .await
                                   .unwrap();
 
@@ -118,6 +132,20 @@ impl Stream {
                             self.current_submission = Some(submission.clone());
                             self.current_testcase = Some(test_case.clone());
 
+                            let limits = &config::get().limits;
+                            let time_limit = calibration::scale_time_limit(
+                                test_case.runtime.map(|v| v as u64).unwrap_or(limits.time_limit_ms),
+                                self.speed_factor,
+                            );
+                            self.start_watchdog(time_limit, submission.lang);
+                            tracing::debug!(
+                                submission_id = submission.id,
+                                testcase_id = test_case.id,
+                                time_limit,
+                                watchdog_secs = self.watchdog_budget.as_secs_f64(),
+                                "task sent"
+                            );
+
                             let msg = Message::SetTask(MsgSetTask {
                                     submission_id: submission.id as i64,
                                     testcase_id: test_case.id as i64,
@@ -127,26 +155,48 @@ impl Stream {
                                     input: test_case.input,
                                     expect_output: test_case.output,
 
-                                    time_limit: test_case.runtime.unwrap_or(15) as u64,
-                                    memory_limit: test_case.memory_limit.unwrap_or(1_000_000_000) as u64,
+                                    time_limit,
+                                    memory_limit: test_case.memory_limit.map(|v| v as u64).unwrap_or(limits.memory_limit),
 
                                     is_decimal_mode: test_case.is_decimal_mode == 1,
                             });
 
-                            let body: MessageBody = msg.into();
-                            body.encode(&mut self.send_buf);
-
-                            while let Ok(len) = self.stream.write_buf(&mut self.send_buf).await {
-                                if self.send_buf.is_empty() { break; }
+                            self.send(msg).await;
+                        }
+                        Some(ChannelMessage::Calibrate) => {
+                            // 매니저 쪽에서는 작업 중으로 표시했으므로 못 하더라도 응답은 보내야 함
+                            if self.current_submission.is_some() || self.is_calibrating {
+                                self.tx_manager
+                                    .send(ChannelMessage::Calibrated(self.stream_id, self.speed_factor))
+                                    .await
+                                    .unwrap();
 
-                                if len == 0 { break; }
+                                continue;
                             }
 
-                            self.start_dt = Some(std::time::Instant::now());
+                            self.is_calibrating = true;
+
+                            let calibration = &config::get().calibration;
+                            self.start_watchdog(calibration.time_limit_ms, SubmissionLanguage::C);
+
+                            let msg = Message::SetTask(calibration::benchmark_task(
+                                calibration.time_limit_ms,
+                                config::get().limits.memory_limit,
+                            ));
+                            self.send(msg).await;
                         }
-                        _ => {
-                            todo!()
+                        Some(ChannelMessage::Drain(_)) => {
+                            self.is_draining = true;
+
+                            // 진행중인 작업이 없으면 바로 종료, 있으면 결과를 받은 뒤에 종료
+                            if self.current_submission.is_none() {
+                                self.send(Message::Shutdown(MsgShutdown {})).await;
+                                return;
+                            }
                         }
+                        // 매니저 쪽 채널이 닫힘
+                        None => return,
+                        _ => (),
                     }
                 },
                 data = self.recv() => {
@@ -155,14 +205,16 @@ impl Stream {
                     if (self.process_socket(data.unwrap()).await).is_err() {
                         return;
                     };
+
+                    if self.is_draining && self.current_submission.is_none() {
+                        self.send(Message::Shutdown(MsgShutdown {})).await;
+                        return;
+                    }
                 }
-                _ = interval.tick(), if self.is_started => {
-                    if let Some(dt) = self.start_dt {
-                        if dt.elapsed().as_secs() > 180 {
-                            // 180초 이상 반응이 없으면 워커가 죽은거로 판단
-                            // (위에서 shutdown 처리돼서 rejudge 걸림)
-                            return;
-                        }
+                _ = interval.tick(), if self.deadline.is_some() => {
+                    if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                        self.expire().await;
+                        return;
                     }
                 }
             )
@@ -176,9 +228,18 @@ impl Stream {
         // 컴파일 로그
         // 결과
 
+        if let Message::ResultSuccess(_) | Message::ResultFailed(_) = msg {
+            self.deadline = None;
+        }
+
         match msg {
-            Message::SetTaskAck(_) => {
-                self.is_started = true;
+            Message::SetTaskAck(_) => {}
+            Message::ResultSuccess(msg) if self.is_calibrating => {
+                self.finish_calibration(Some(msg.0.time_used)).await;
+            }
+            Message::ResultFailed(msg) if self.is_calibrating => {
+                tracing::warn!(verdict = ?msg.0.result, "calibration failed");
+                self.finish_calibration(None).await;
             }
             Message::ResultSuccess(msg) => {
                 let msg = msg.0;
@@ -188,13 +249,21 @@ impl Stream {
                     self.current_testcase.as_ref().unwrap().id,
                     true,
                     Some(msg.output_run),
-                    Some(msg.time_used as usize),
+                    Some(calibration::normalize_runtime(msg.time_used, self.speed_factor) as usize),
                     Some(msg.memory_used as usize),
                     Some(msg.output_compile),
                     msg.judge_server_id,
                 );
                 let result_inner = TestCaseJudgeResultInner::Accepted;
 
+                tracing::debug!(
+                    submission_id = result.submit_id,
+                    testcase_id = result.testcase_id,
+                    verdict = ?result_inner,
+                    time_used = msg.time_used,
+                    "result received"
+                );
+
                 self.tx_manager
                     .send(ChannelMessage::WorkDone(
                         self.stream_id,
@@ -215,7 +284,7 @@ impl Stream {
                     self.current_testcase.as_ref().unwrap().id,
                     false,
                     Some(msg.output_run),
-                    Some(msg.time_used as usize),
+                    Some(calibration::normalize_runtime(msg.time_used, self.speed_factor) as usize),
                     Some(msg.memory_used as usize),
                     Some(msg.output_compile),
                     msg.judge_server_id,
@@ -223,6 +292,14 @@ impl Stream {
 
                 let result_inner = msg.result;
 
+                tracing::debug!(
+                    submission_id = result.submit_id,
+                    testcase_id = result.testcase_id,
+                    verdict = ?result_inner,
+                    time_used = msg.time_used,
+                    "result received"
+                );
+
                 self.tx_manager
                     .send(ChannelMessage::WorkDone(
                         self.stream_id,
@@ -246,6 +323,86 @@ impl Stream {
         Ok(())
     }
 
+    /// 시간 제한(ms, 이 워커 기준) + 언어별 컴파일 시간 + 여유 시간 안에 결과가 와야 함
+    fn start_watchdog(&mut self, time_limit_ms: u64, lang: SubmissionLanguage) {
+        let config = config::get();
+
+        self.watchdog_budget = watchdog_budget(
+            time_limit_ms,
+            config.compile_budget.of(lang),
+            config.listener.watchdog_grace(),
+        );
+        self.deadline = Some(Instant::now() + self.watchdog_budget);
+    }
+
+    /// 기한 안에 결과가 오지 않음. 진행중인 작업은 사유와 함께 매니저에게 돌려주고 연결을 끊음
+    async fn expire(&mut self) {
+        self.deadline = None;
+        let reason = format!(
+            "watchdog expired on channel {}: no result within {:.1}s",
+            self.stream_id,
+            self.watchdog_budget.as_secs_f64()
+        );
+
+        if self.is_calibrating {
+            tracing::warn!(%reason, "calibration failed");
+            self.finish_calibration(None).await;
+        }
+
+        if let (Some(submission), Some(testcase)) =
+            (self.current_submission.take(), self.current_testcase.take())
+        {
+            tracing::warn!(
+                submission_id = submission.id,
+                testcase_id = testcase.id,
+                %reason,
+                "watchdog expired"
+            );
+            self.tx_manager
+                .send(ChannelMessage::Expired(
+                    self.stream_id,
+                    submission,
+                    testcase,
+                    reason,
+                ))
+                .await
+                .unwrap();
+        }
+    }
+
+    /// 측정에 실패하면 (`None`) 이전 값을 그대로 씀
+    async fn finish_calibration(&mut self, time_used: Option<u64>) {
+        self.is_calibrating = false;
+
+        if let Some(time_used) = time_used {
+            self.speed_factor =
+                calibration::speed_factor(time_used, config::get().calibration.reference_ms);
+        }
+
+        self.tx_manager
+            .send(ChannelMessage::Calibrated(
+                self.stream_id,
+                self.speed_factor,
+            ))
+            .await
+            .unwrap();
+    }
+
+    async fn send(&mut self, msg: Message) {
+        let body: MessageBody = msg.into();
+        body.encode(&mut self.send_buf);
+
+        while let Ok(len) = self.stream.write_buf(&mut self.send_buf).await {
+            if self.send_buf.is_empty() {
+                break;
+            }
+
+            if len == 0 {
+                break;
+            }
+        }
+    }
+
     async fn recv(&mut self) -> Result<Message, ()> {
         while let Ok(len) = self.stream.read_buf(&mut self.recv_buf).await {
             if len == 0 {
@@ -261,3 +418,37 @@ impl Stream {
         Err(())
     }
 }
+
+/// 워커가 결과를 보내야 하는 기한. `time_limit_ms`는 워커 속도에 맞춰 늘린 값
+fn watchdog_budget(time_limit_ms: u64, compile_budget: Duration, grace: Duration) -> Duration {
+    Duration::from_millis(time_limit_ms) + compile_budget + grace
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn watchdog_budget_scales_time_limit_only() {
+        let compile = Duration::from_secs(10);
+        let grace = Duration::from_secs(5);
+
+        // 느린 워커 (1.5배)에서 2초짜리 테스트케이스는 3초 + 컴파일 10초 + 여유 5초
+        let time_limit_ms = calibration::scale_time_limit(2000, 1.5);
+        assert_eq!(time_limit_ms, 3000);
+        assert_eq!(
+            watchdog_budget(time_limit_ms, compile, grace),
+            Duration::from_secs(18)
+        );
+
+        // 빠른 워커는 시간 제한만 줄어듦
+        let time_limit_ms = calibration::scale_time_limit(2000, 0.5);
+        assert_eq!(
+            watchdog_budget(time_limit_ms, compile, grace),
+            Duration::from_secs(16)
+        );
+
+        // 올림해서 1ms라도 짧아지지 않음
+        assert_eq!(calibration::scale_time_limit(1001, 1.5), 1502);
+    }
+}
@@ -0,0 +1,21 @@
+use tracing_subscriber::EnvFilter;
+
+use crate::config::{LogFormat, LoggingConfig};
+
+/// 시작할 때 한번만 호출. 설정을 읽기 전의 오류는 표준 에러로 직접 출력함
+///
+/// 제출/테스트케이스/워커 관련 로그에는 `submission_id`, `testcase_id`, `channel_id` 필드가 붙으므로
+/// JSON 형식에서 이 필드로 걸러내면 제출 하나의 채점 과정을 볼 수 있음
+pub fn init(config: &LoggingConfig) {
+    let filter = EnvFilter::new(&config.level);
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+
+    match config.format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .init(),
+    }
+}
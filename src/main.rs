@@ -1,12 +1,33 @@
+mod admin;
+mod calibration;
+mod clock;
+mod config;
+mod console;
 mod db;
+mod events;
 mod listener;
+mod logging;
+mod metrics;
+mod migrate;
+mod postgres;
 mod protocol;
+mod queue;
+mod scheduler;
+mod simulation;
+mod sqlite;
+mod store;
 mod task_manager;
 mod types;
+mod webhook;
 
-use rand::seq::SliceRandom;
-use rand::thread_rng;
+use std::sync::Arc;
 
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use admin::AdminRequest;
+use config::DatabaseBackend;
+use store::JudgeStore;
 use types::*;
 
 #[derive(Debug)]
@@ -14,7 +35,7 @@ pub enum ChannelMessage {
     NewChannel(TX, bool),
     SetChannelId(usize),
     WorkStart(Submission, TestCase),
-    Refuse(Submission, TestCase),
+    Refuse(usize, Submission, TestCase),
     ReJudge(Submission, TestCase),
     WorkDone(
         usize,
@@ -24,147 +45,198 @@ pub enum ChannelMessage {
         TestCaseJudgeResultInner,
     ),
     Shutdown(usize, Option<Submission>, Option<TestCase>),
+    /// 워커 -> 매니저. 기한 안에 결과가 오지 않은 작업 (channel_id, .., 사유). 이후 연결이 끊김
+    Expired(usize, Submission, TestCase, String),
+    /// 관리자 -> 매니저 -> 워커 순으로 전달됨. 현재 테스트케이스까지만 채점하고 연결을 끊음
+    Drain(usize),
+    /// 매니저 -> 워커. 속도 측정용 벤치마크를 돌림
+    Calibrate,
+    /// 워커 -> 매니저. (channel_id, speed_factor)
+    Calibrated(usize, f64),
+    /// 관리자 API -> 매니저
+    Admin(AdminRequest),
 }
-struct Channel {
-    channel_id: usize,
-    tx: TX,
-    is_working: bool,
-    is_precise_measurement: bool,
-    current_submission_id: Option<usize>,
-}
-
 type TX = tokio::sync::mpsc::Sender<ChannelMessage>;
 type RX = tokio::sync::mpsc::Receiver<ChannelMessage>;
 
 fn main() {
+    // `judge simulate <시나리오.toml>`: DB와 워커 없이 스케줄러만 돌려봄 (simulation.rs)
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some("simulate") = args.first().map(String::as_str) {
+        let Some(path) = args.get(1) else {
+            eprintln!("usage: judge simulate <scenario.toml>");
+            std::process::exit(2);
+        };
+        std::process::exit(simulation::main(path));
+    }
+    // `judge migrate`: 스키마를 만들거나 최신 버전으로 올리고 끝냄 (migrate.rs)
+    let command = match args.first().map(String::as_str) {
+        Some("migrate") => Command::Migrate,
+        _ => Command::Run,
+    };
+
+    let config = match config::Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("config error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    config::init(config);
+    logging::init(&config::get().logging);
+
     let rt = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
         .build()
         .unwrap();
 
-    rt.block_on(main_async());
+    rt.block_on(main_async(command));
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Run,
+    Migrate,
 }
-async fn main_async() {
-    let mut interval = tokio::time::interval(std::time::Duration::from_millis(1000));
-
-    let mut channels: Vec<Channel> = vec![];
-    let mut channel_id: usize = 1;
-    let (tx, mut rx) = tokio::sync::mpsc::channel::<ChannelMessage>(128);
-
-    let mut task_manager = task_manager::TaskManager::new();
 
-    tokio::spawn(listener::Listener::new(tx).run());
+async fn main_async(command: Command) {
+    let database = &config::get().database;
 
-    loop {
-        tokio::select! {
-            Some(rx_msg) = rx.recv() => {
-              // eprintln!("Received message: {:?}", rx_msg);
-                match rx_msg {
-                    ChannelMessage::NewChannel(tx, is_precise_measurement) => {
-                        tx.send(ChannelMessage::SetChannelId(channel_id)).await.unwrap();
-
-                        channels.push(Channel {
-                            channel_id,
-                            tx,
-                            is_working: false,
-                            is_precise_measurement,
-                            current_submission_id: None,
-                        });
-
-                        channel_id += 1;
-                    }
-                    ChannelMessage::WorkDone(channel_id, submission, _testcase, result, result_inner) => {
-                        if let Some(channel) = channels.iter_mut().find(|channel| channel.channel_id == channel_id) {
-                            channel.is_working = false;
-
-                            task_manager.add_result(submission.id, result, result_inner).await;
-                        }
-                    }
-                    ChannelMessage::Shutdown(channel_id, submission, testcase) => {
-                        if let Some(pos) = channels.iter().position(|channel| channel.channel_id == channel_id) {
-                            channels.remove(pos);
-
-                            if let (Some(submission), Some(testcase)) = (submission, testcase) {
-                                task_manager.force_rejudge(submission, testcase);
-                            }
-                        }
-                    }
-                    ChannelMessage::Refuse(submission, testcase) => {
-                        task_manager.force_rejudge(submission, testcase);
-                    }
-                    ChannelMessage::ReJudge(submission, testcase) => {
-                        task_manager.force_rejudge(submission, testcase);
-                    }
-                    _ => ()
-                }
+    match database.backend() {
+        Some(DatabaseBackend::Mysql) => {
+            if let Err(e) = db::init(database) {
+                tracing::error!(error = %e, "cannot create database pool");
+                std::process::exit(1);
             }
-            _ = interval.tick() => { // 0.1초마다 큐 채우기
-              // println!("** last task manager {:?}", task_manager.task_quick);
-
-
-                // early-return 상황이 있을수 있어서 우선 검사
-                task_manager.process().await;
-
-                // Testcase는 상황에 따라서 실시간 수정 될 수도 있음. 그렇기 때문에, 루프 안에서만 캐싱 되도록 함
-                let (mut available_precise, mut available_quick): (Vec<_>, Vec<_>) = channels.iter_mut().filter(|channel| !channel.is_working).partition(|channel| channel.is_precise_measurement);
-                if available_precise.is_empty() && available_quick.is_empty() {
-                    continue;
+            start(db::MysqlStore, command).await
+        }
+        Some(DatabaseBackend::Sqlite) => {
+            let path = database.sqlite_path().unwrap_or_default();
+            match sqlite::SqliteStore::open(path) {
+                Ok(store) => start(store, command).await,
+                Err(e) => {
+                    tracing::error!(path, error = %e, "cannot open sqlite database");
+                    std::process::exit(1);
                 }
+            }
+        }
+        Some(DatabaseBackend::Postgres) => match postgres::PostgresStore::open(database) {
+            Ok(store) => start(store, command).await,
+            Err(e) => {
+                tracing::error!(error = %e, "cannot open postgres database");
+                std::process::exit(1);
+            }
+        },
+        // config::validate에서 걸러짐
+        None => unreachable!(),
+    }
+}
 
-                let (mut task_precise, mut task_quick) = db::list_submissions(available_precise.len().max(8), available_quick.len().max(8)).await;
-                if (task_precise.len() + task_quick.len()) > 0 {
-                    let mut queued_mark: Vec<i32> = Vec::with_capacity(available_precise.len() + available_quick.len());
-                    for task in task_precise.drain(..) {
-                        queued_mark.push(task.id);
-                        task_manager.add_submissions(task).await;
-                    }
-                    for task in task_quick.drain(..) {
-                        queued_mark.push(task.id);
-                        task_manager.add_submissions(task).await;
-                    }
-
-                    db::mark_submission_queued(queued_mark).await;
+/// 저장소를 정한 뒤로는 백엔드와 상관없이 같음
+async fn start<S: JudgeStore>(store: S, command: Command) {
+    if command == Command::Migrate {
+        match store.migrate().await {
+            Ok(applied) if applied.is_empty() => {
+                tracing::info!(version = migrate::VERSION, "database schema is up to date")
+            }
+            Ok(applied) => {
+                for migration in applied {
+                    tracing::info!(
+                        version = migration.version,
+                        name = migration.name,
+                        "applied migration"
+                    );
                 }
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "migration failed");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
 
-                // starving이 생기지 않도록 두개 다 들고오는 처리
+    // 스키마가 다르면 쿼리가 중간에 실패하므로 아예 시작하지 않음
+    match store.schema_version().await {
+        Ok(version) if version == migrate::VERSION => (),
+        Ok(version) if version < migrate::VERSION => {
+            tracing::error!(
+                version,
+                expected = migrate::VERSION,
+                "database schema is out of date, run `judge migrate`"
+            );
+            std::process::exit(1);
+        }
+        Ok(version) => {
+            tracing::error!(
+                version,
+                expected = migrate::VERSION,
+                "database schema is newer than this coordinator"
+            );
+            std::process::exit(1);
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "cannot read database schema version");
+            std::process::exit(1);
+        }
+    }
 
-                task_manager.process().await;
+    run(store).await
+}
 
-                // 특정 서버에서만 (id가 낮은 서버) 작동되지 않도록 - 모든 서버에서 작동되도록 셔플
-                available_precise.shuffle(&mut thread_rng());
-                available_quick.shuffle(&mut thread_rng());
+async fn run<S: JudgeStore>(store: S) {
+    let poll = tokio::time::sleep(config::get().scheduler.poll_interval_min());
+    tokio::pin!(poll);
 
-                let mut redo_precise = Vec::with_capacity(8);
-                let mut redo_quick = Vec::with_capacity(8);
+    let mut lease_renewal = tokio::time::interval(config::get().coordinator.renew_interval());
+    // 첫 tick은 바로 실행되므로 시작할 때 복구하는 것도 겸함
+    let mut reaper = tokio::time::interval(config::get().coordinator.reap_interval());
 
-                while let Some(task) = task_manager.task_precise.pop() {
-                    if let Some(channel) = available_precise.pop() {
-                        channel.is_working = true;
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<ChannelMessage>(128);
 
-                        if (channel.tx.send(ChannelMessage::WorkStart(task.0.clone(), task.1.clone())).await).is_err() {
-                            redo_precise.push((task.0.clone(), task.1.clone()));
-                        }
-                    } else {
-                        redo_precise.push((task.0.clone(), task.1.clone()));
-                        break;
-                    }
-                }
-                while let Some(task) = task_manager.task_quick.pop() {
-                    if let Some(channel) = available_quick.pop().or(available_precise.pop()) {
-                        channel.is_working = true;
-
-                        if (channel.tx.send(ChannelMessage::WorkStart(task.0.clone(), task.1.clone())).await).is_err() {
-                            redo_quick.push((task.0.clone(), task.1.clone()));
-                        }
-                    } else {
-                        redo_quick.push((task.0.clone(), task.1.clone()));
-                        break;
-                    }
-                }
+    let store = Arc::new(store);
+    let (tx_events, _) = tokio::sync::broadcast::channel(1024);
+    let (tx_webhooks, webhooks) = webhook::channel();
+    let mut scheduler = scheduler::Scheduler::new(
+        Arc::new(clock::SystemClock),
+        store.clone(),
+        StdRng::from_entropy(),
+        tx_events.clone(),
+        tx_webhooks,
+    );
+
+    if config::get().admin.enabled {
+        tokio::spawn(admin::Admin::new(tx.clone()).run());
+    }
+    if !config::get().events.bind.is_empty() {
+        tokio::spawn(events::Events::new(tx_events).run());
+    }
+    if !config::get().webhooks.targets.is_empty() {
+        tokio::spawn(webhooks.run());
+    }
+    if !config::get().metrics.bind.is_empty() {
+        tokio::spawn(metrics::run());
+    }
+    tokio::spawn(console::Console::new(tx.clone()).run());
+    tokio::spawn(listener::Listener::new(tx).run());
 
-              // println!("redo quick {:?}", redo_quick);
-                redo_precise.drain(..).for_each(|(submission, testcase)| task_manager.force_rejudge(submission, testcase));
-                redo_quick.drain(..).for_each(|(submission, testcase)| task_manager.force_rejudge(submission, testcase));
+    loop {
+        tokio::select! {
+            Some(rx_msg) = rx.recv() => {
+                scheduler.handle(rx_msg).await;
+            }
+            _ = lease_renewal.tick() => {
+                scheduler.renew_leases().await;
+            }
+            _ = reaper.tick(), if !scheduler.is_intake_paused() => {
+                scheduler.reap().await;
+            }
+            _ = &mut poll => {
+                let poll_interval = scheduler.poll().await;
+                poll.as_mut().reset(tokio::time::Instant::now() + poll_interval);
+            }
+            // 새 제출 알림을 받으면 다음 폴링을 바로 함
+            _ = store.wait_for_submission() => {
+                poll.as_mut().reset(tokio::time::Instant::now());
             }
         }
     }
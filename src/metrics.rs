@@ -0,0 +1,209 @@
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use axum::{http::header, response::IntoResponse, routing, Router};
+use prometheus::{
+    exponential_buckets, Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+
+use crate::{config, queue::PriorityClass, types::*};
+
+/// 코디네이터 지표. `GET /metrics`로 Prometheus 형식으로 내보냄
+pub struct Metrics {
+    registry: Registry,
+
+    /// (queue, class)
+    pub queue_depth: IntGaugeVec,
+    /// (class) 큐에 들어간 뒤 워커에 배정될 때까지
+    pub dispatch_latency: HistogramVec,
+    /// (language) 워커가 잰 테스트케이스 실행 시간 (기준 머신 기준으로 보정된 값)
+    pub exec_time: HistogramVec,
+    /// (verdict)
+    pub verdicts: IntCounterVec,
+    /// (state)
+    pub workers: IntGaugeVec,
+    /// (statement)
+    pub db_latency: HistogramVec,
+    /// (statement)
+    pub db_errors: IntCounterVec,
+    /// (reason) 워커 문제로 다시 넣은 테스트케이스, 관리자가 요청한 재채점
+    pub rejudges: IntCounterVec,
+    /// (queue, class) 최대 대기 시간을 넘긴 작업. 작업마다 한번만 셈
+    pub queue_wait_exceeded: IntCounterVec,
+}
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+pub fn get() -> &'static Metrics {
+    &METRICS
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("judge".to_string()), None).unwrap();
+
+        let queue_depth = IntGaugeVec::new(
+            Opts::new("queue_depth", "Tasks waiting in the queue"),
+            &["queue", "class"],
+        )
+        .unwrap();
+        let dispatch_latency = HistogramVec::new(
+            HistogramOpts::new(
+                "dispatch_latency_seconds",
+                "Time from enqueue to assignment to a worker",
+            )
+            .buckets(exponential_buckets(0.01, 2.0, 16).unwrap()),
+            &["class"],
+        )
+        .unwrap();
+        let exec_time = HistogramVec::new(
+            HistogramOpts::new("testcase_exec_seconds", "Testcase execution time")
+                .buckets(exponential_buckets(0.001, 2.0, 16).unwrap()),
+            &["language"],
+        )
+        .unwrap();
+        let verdicts = IntCounterVec::new(
+            Opts::new("verdicts_total", "Testcase verdicts"),
+            &["verdict"],
+        )
+        .unwrap();
+        let workers = IntGaugeVec::new(
+            Opts::new("workers", "Connected workers by state"),
+            &["state"],
+        )
+        .unwrap();
+        let db_latency = HistogramVec::new(
+            HistogramOpts::new("db_query_duration_seconds", "Database query latency")
+                .buckets(exponential_buckets(0.0005, 2.0, 16).unwrap()),
+            &["statement"],
+        )
+        .unwrap();
+        let db_errors = IntCounterVec::new(
+            Opts::new("db_errors_total", "Failed database queries"),
+            &["statement"],
+        )
+        .unwrap();
+        let rejudges = IntCounterVec::new(
+            Opts::new("rejudges_total", "Requeued testcases and admin rejudges"),
+            &["reason"],
+        )
+        .unwrap();
+        let queue_wait_exceeded = IntCounterVec::new(
+            Opts::new(
+                "queue_wait_exceeded_total",
+                "Tasks that waited longer than scheduler.max_queue_wait_secs",
+            ),
+            &["queue", "class"],
+        )
+        .unwrap();
+
+        registry.register(Box::new(queue_depth.clone())).unwrap();
+        registry
+            .register(Box::new(dispatch_latency.clone()))
+            .unwrap();
+        registry.register(Box::new(exec_time.clone())).unwrap();
+        registry.register(Box::new(verdicts.clone())).unwrap();
+        registry.register(Box::new(workers.clone())).unwrap();
+        registry.register(Box::new(db_latency.clone())).unwrap();
+        registry.register(Box::new(db_errors.clone())).unwrap();
+        registry.register(Box::new(rejudges.clone())).unwrap();
+        registry
+            .register(Box::new(queue_wait_exceeded.clone()))
+            .unwrap();
+
+        Self {
+            registry,
+            queue_depth,
+            dispatch_latency,
+            exec_time,
+            verdicts,
+            workers,
+            db_latency,
+            db_errors,
+            rejudges,
+            queue_wait_exceeded,
+        }
+    }
+
+    pub fn observe_dispatch(&self, class: PriorityClass, waited: Duration) {
+        self.dispatch_latency
+            .with_label_values(&[&format!("{:?}", class)])
+            .observe(waited.as_secs_f64());
+    }
+
+    pub fn observe_result(
+        &self,
+        lang: SubmissionLanguage,
+        result: &TestCaseJudgeResult,
+        result_inner: &TestCaseJudgeResultInner,
+    ) {
+        if let Some(runtime) = result.runtime {
+            self.exec_time
+                .with_label_values(&[&String::from(lang)])
+                .observe(runtime as f64 / 1000.0);
+        }
+        self.verdicts
+            .with_label_values(&[&format!("{:?}", result_inner)])
+            .inc();
+    }
+
+    /// `statement`는 SQL의 첫 단어 (select, update, ...)
+    pub fn observe_db(&self, sql: &str, elapsed: Duration, is_err: bool) {
+        let statement = sql.split_whitespace().next().unwrap_or("").to_lowercase();
+
+        self.db_latency
+            .with_label_values(&[&statement])
+            .observe(elapsed.as_secs_f64());
+        if is_err {
+            self.db_errors.with_label_values(&[&statement]).inc();
+        }
+    }
+
+    pub fn count_rejudge(&self, reason: &str) {
+        self.rejudges.with_label_values(&[reason]).inc();
+    }
+
+    pub fn count_queue_wait_exceeded(&self, queue: &str, class: PriorityClass) {
+        self.queue_wait_exceeded
+            .with_label_values(&[queue, &format!("{:?}", class)])
+            .inc();
+    }
+
+    fn encode(&self) -> String {
+        let mut buf = vec![];
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buf)
+            .unwrap();
+
+        String::from_utf8(buf).unwrap()
+    }
+}
+
+pub fn router<S: Clone + Send + Sync + 'static>() -> Router<S> {
+    Router::new().route("/metrics", routing::get(metrics))
+}
+
+async fn metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+        get().encode(),
+    )
+}
+
+/// `metrics.bind`가 지정되면 관리자 API와 별도로 `/metrics`만 여는 서버 (Prometheus가 다른 머신에 있을 때)
+pub async fn run() {
+    let bind = &config::get().metrics.bind;
+    let tcp_listener = match tokio::net::TcpListener::bind(bind).await {
+        Ok(tcp_listener) => tcp_listener,
+        Err(e) => {
+            tracing::error!(bind, error = %e, "metrics: cannot bind");
+            return;
+        }
+    };
+    tracing::info!(bind, "metrics listening");
+
+    if let Err(e) = axum::serve(tcp_listener, router::<()>()).await {
+        tracing::error!(error = %e, "metrics stopped");
+    }
+}
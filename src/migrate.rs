@@ -0,0 +1,207 @@
+//! DB 스키마 마이그레이션
+//!
+//! SQL은 `migrations/<백엔드>/`에 있고 바이너리에 포함됨. 버전은 백엔드마다 같은 번호를 쓰고,
+//! 적용한 버전은 `schema_version` 테이블에 남김. `judge migrate`로 적용하고,
+//! 코디네이터는 시작할 때 버전이 `VERSION`과 같은지만 확인함
+//!
+//! 마이그레이션 도입 전부터 쓰던 DB는 `0001_initial`이 테이블을 건너뛰므로, 그 뒤에 쓰게 된 컬럼은
+//! `LEGACY_COLUMNS`로 따로 맞춤
+
+/// 이 코디네이터가 기대하는 스키마 버전
+pub const VERSION: u32 = 3;
+
+#[derive(Debug)]
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+pub const MYSQL: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial",
+        sql: include_str!("../migrations/mysql/0001_initial.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "testcase_judge_attempt",
+        sql: include_str!("../migrations/mysql/0002_testcase_judge_attempt.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "submission_generation",
+        sql: include_str!("../migrations/mysql/0003_submission_generation.sql"),
+    },
+];
+
+pub const SQLITE: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial",
+        sql: include_str!("../migrations/sqlite/0001_initial.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "testcase_judge_attempt",
+        sql: include_str!("../migrations/sqlite/0002_testcase_judge_attempt.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "submission_generation",
+        sql: include_str!("../migrations/sqlite/0003_submission_generation.sql"),
+    },
+];
+
+pub const POSTGRES: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial",
+        sql: include_str!("../migrations/postgres/0001_initial.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "testcase_judge_attempt",
+        sql: include_str!("../migrations/postgres/0002_testcase_judge_attempt.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "submission_generation",
+        sql: include_str!("../migrations/postgres/0003_submission_generation.sql"),
+    },
+];
+
+// 마이그레이션을 추가하고 VERSION을 올리지 않았거나, 한 백엔드에만 추가한 경우
+const _: () = assert!(
+    MYSQL.len() == VERSION as usize
+        && SQLITE.len() == VERSION as usize
+        && POSTGRES.len() == VERSION as usize
+);
+
+/// 마이그레이션 도입 전 스키마에는 없을 수 있는 컬럼
+///
+/// `judge migrate`는 마이그레이션을 적용하기 전에 이미 있는 테이블에서 빠진 컬럼만 `ALTER TABLE ... ADD COLUMN`으로 추가함.
+/// 테이블이 없으면 `0001_initial`이 만들므로 건너뜀. 매번 확인하므로 `0001_initial`만 기록되고
+/// 다음 마이그레이션이 실패한 DB도 다시 `judge migrate`를 돌리면 됨.
+/// 여기에는 마이그레이션 도입 전에 쓰기 시작한 컬럼만 둠. 그 뒤의 컬럼은 마이그레이션으로 추가함
+#[derive(Debug)]
+pub struct LegacyColumn {
+    pub table: &'static str,
+    pub name: &'static str,
+    /// 백엔드별 컬럼 정의 (이름 뒤에 오는 부분). 기존 행이 있으므로 NOT NULL이면 기본값이 있어야 함
+    pub mysql: &'static str,
+    pub sqlite: &'static str,
+    pub postgres: &'static str,
+}
+impl LegacyColumn {
+    /// 테이블의 지금 컬럼(`existing`)에 없는지. 테이블이 없으면 (컬럼이 하나도 없으면) `false`
+    pub fn is_missing(&self, existing: &[String]) -> bool {
+        !existing.is_empty()
+            && !existing
+                .iter()
+                .any(|column| column.eq_ignore_ascii_case(self.name))
+    }
+}
+
+pub const LEGACY_COLUMNS: &[LegacyColumn] = &[
+    LegacyColumn {
+        table: "Submit",
+        name: "score",
+        mysql: "int DEFAULT NULL",
+        sqlite: "INTEGER",
+        postgres: "INTEGER",
+    },
+    LegacyColumn {
+        table: "Submit",
+        name: "queued",
+        mysql: "tinyint NOT NULL DEFAULT '0' COMMENT '코디네이터가 가져감'",
+        sqlite: "INTEGER NOT NULL DEFAULT 0",
+        postgres: "SMALLINT NOT NULL DEFAULT 0",
+    },
+    LegacyColumn {
+        table: "Submit",
+        name: "needs_review",
+        mysql: "tinyint NOT NULL DEFAULT '0' COMMENT '교차 검증 결과가 갈림'",
+        sqlite: "INTEGER NOT NULL DEFAULT 0",
+        postgres: "SMALLINT NOT NULL DEFAULT 0",
+    },
+    LegacyColumn {
+        table: "Submit",
+        name: "claimed_by",
+        mysql: "varchar(64) DEFAULT NULL COMMENT '채점 중인 코디네이터'",
+        sqlite: "TEXT",
+        postgres: "VARCHAR(64)",
+    },
+    LegacyColumn {
+        table: "Submit",
+        name: "claim_expires_at",
+        mysql: "datetime DEFAULT NULL COMMENT '이 시각까지 갱신되지 않으면 다른 코디네이터가 가져갈 수 있음'",
+        sqlite: "TEXT",
+        postgres: "TIMESTAMPTZ",
+    },
+    LegacyColumn {
+        table: "Testcase",
+        name: "is_decimal_mode",
+        mysql: "int NOT NULL DEFAULT '0' COMMENT '실수 출력을 오차 범위 안에서 비교'",
+        sqlite: "INTEGER NOT NULL DEFAULT 0",
+        postgres: "INTEGER NOT NULL DEFAULT 0",
+    },
+    LegacyColumn {
+        table: "Testcase_judge",
+        name: "testcase_id",
+        mysql: "int NOT NULL DEFAULT '0'",
+        sqlite: "INTEGER NOT NULL DEFAULT 0",
+        postgres: "INTEGER NOT NULL DEFAULT 0",
+    },
+    LegacyColumn {
+        table: "Testcase_judge",
+        name: "memory",
+        mysql: "int DEFAULT NULL",
+        sqlite: "INTEGER",
+        postgres: "INTEGER",
+    },
+    // SQLite는 ADD COLUMN에서 CURRENT_TIMESTAMP를 기본값으로 쓸 수 없음. 저장할 때 채움
+    LegacyColumn {
+        table: "Testcase_judge",
+        name: "judge_at",
+        mysql: "datetime NOT NULL DEFAULT CURRENT_TIMESTAMP",
+        sqlite: "TEXT",
+        postgres: "TIMESTAMPTZ NOT NULL DEFAULT now()",
+    },
+    LegacyColumn {
+        table: "Testcase_judge",
+        name: "judge_server_id",
+        mysql: "varchar(64) NOT NULL DEFAULT ''",
+        sqlite: "TEXT NOT NULL DEFAULT ''",
+        postgres: "VARCHAR(64) NOT NULL DEFAULT ''",
+    },
+    LegacyColumn {
+        table: "Testcase_judge",
+        name: "result_extra",
+        mysql: "varchar(128) DEFAULT NULL",
+        sqlite: "TEXT",
+        postgres: "VARCHAR(128)",
+    },
+    LegacyColumn {
+        table: "Testcase_judge",
+        name: "attempt",
+        mysql: "int NOT NULL DEFAULT '0' COMMENT '교차 검증시 몇 번째 실행인지'",
+        sqlite: "INTEGER NOT NULL DEFAULT 0",
+        postgres: "INTEGER NOT NULL DEFAULT 0",
+    },
+];
+
+/// 적용한 버전을 남기는 테이블. 세 백엔드에서 그대로 실행됨
+pub const VERSION_TABLE: &str = "CREATE TABLE IF NOT EXISTS schema_version (
+    version INTEGER NOT NULL PRIMARY KEY,
+    name VARCHAR(128) NOT NULL,
+    applied_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+)";
+
+/// `current` 다음 버전부터 순서대로
+pub fn pending(migrations: &'static [Migration], current: u32) -> Vec<&'static Migration> {
+    migrations
+        .iter()
+        .filter(|migration| migration.version > current)
+        .collect()
+}
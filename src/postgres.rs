@@ -0,0 +1,695 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Once};
+
+use chrono::NaiveDateTime;
+use deadpool_postgres::{GenericClient, Manager, ManagerConfig, Pool, RecyclingMethod, Runtime};
+use futures_util::StreamExt;
+use tokio::sync::Notify;
+use tokio_postgres::types::{FromSql, ToSql};
+use tokio_postgres::{AsyncMessage, NoTls, Row};
+
+use crate::{
+    config::{self, DatabaseConfig},
+    db::{self, DbError},
+    migrate::{self, Migration},
+    store::{Finished, JudgeStore, Verdict},
+    task_manager::TestCaseRun,
+    types::*,
+};
+
+/// 새 제출이 들어오면 `Submit`의 트리거가 이 채널로 제출 번호를 보냄 (migrations/postgres)
+const NOTIFY_CHANNEL: &str = "judge_submit";
+
+/// PostgreSQL `JudgeStore`. 제출은 `FOR UPDATE SKIP LOCKED`로 가져가고,
+/// `LISTEN judge_submit`으로 새 제출이 들어오자마자 폴링을 당김
+pub struct PostgresStore {
+    pool: Pool,
+    pg_config: tokio_postgres::Config,
+    /// 알림을 받는 연결은 처음 `wait_for_submission`을 부를 때 띄움 (`judge migrate`에서는 띄우지 않음)
+    listening: Once,
+    /// 알림 연결이 받은 새 제출
+    submitted: Arc<Notify>,
+}
+impl PostgresStore {
+    /// 풀을 만듦. 실제 연결은 처음 쓸 때 만들어지고, 테이블은 `judge migrate`로 만듦
+    pub fn open(database: &DatabaseConfig) -> Result<Self, DbError> {
+        let pg_config: tokio_postgres::Config = database.url.parse()?;
+
+        let manager = Manager::from_config(
+            pg_config.clone(),
+            NoTls,
+            ManagerConfig {
+                recycling_method: RecyclingMethod::Fast,
+            },
+        );
+        let pool = Pool::builder(manager)
+            .max_size(database.pool_max)
+            .runtime(Runtime::Tokio1)
+            .build()
+            .expect("pool runtime is set");
+
+        Ok(Self {
+            pool,
+            pg_config,
+            listening: Once::new(),
+            submitted: Arc::new(Notify::new()),
+        })
+    }
+
+    /// 풀에서 연결을 얻어 `f`를 실행함. 일시적인 오류면 다시 시도함
+    async fn run<T, F, Fut>(&self, statement: &str, f: F) -> Result<T, DbError>
+    where
+        F: Fn(deadpool_postgres::Client) -> Fut,
+        Fut: std::future::Future<Output = Result<T, DbError>>,
+    {
+        db::retry(statement, || async {
+            let client = self.pool.get().await?;
+            f(client).await
+        })
+        .await
+    }
+
+    /// `db::claim_where`와 같음. 조건의 `$1`, `$2`..는 `params`, 이 코디네이터 id와 lease는 뒤에 붙임
+    async fn claim_where(
+        &self,
+        conditions: &[(&str, &[&(dyn ToSql + Sync)])],
+    ) -> Result<Vec<Vec<Submission>>, DbError> {
+        let coordinator = &config::get().coordinator;
+
+        self.run("claim", |mut client| async move {
+            let tx = client.transaction().await?;
+
+            let mut claimed = Vec::with_capacity(conditions.len());
+            for (condition, params) in conditions {
+                let stmt = tx
+                    .prepare_cached(&format!(
+                        r#"SELECT * FROM "Submit" WHERE {} FOR UPDATE SKIP LOCKED"#,
+                        condition
+                    ))
+                    .await?;
+
+                let mut submissions = Vec::new();
+                for row in tx.query(&stmt, params).await? {
+                    match submission_from_row(&row) {
+                        Ok(submission) => submissions.push(submission),
+                        Err(e) => {
+                            let id = row.try_get::<_, i32>("id").ok();
+                            tracing::error!(submission_id = ?id, error = %e, "cannot read submission, skipping")
+                        }
+                    }
+                }
+                claimed.push(submissions);
+            }
+
+            let ids = claimed
+                .iter()
+                .flatten()
+                .map(|submission: &Submission| submission.id)
+                .collect::<Vec<_>>();
+            if !ids.is_empty() {
+                tx.execute(
+                    r#"UPDATE "Submit" SET queued = 1, claimed_by = $1, claim_expires_at = now() + $2::int8 * interval '1 second' WHERE id = ANY($3)"#,
+                    &[&coordinator.id, &(coordinator.lease_secs as i64), &ids],
+                )
+                .await?;
+            }
+
+            tx.commit().await?;
+            Ok(claimed)
+        })
+        .await
+    }
+}
+
+impl JudgeStore for PostgresStore {
+    async fn claim_submissions(
+        &self,
+        precise_avail: usize,
+        quick_avail: usize,
+    ) -> Result<(Vec<Submission>, Vec<Submission>), DbError> {
+        let (precise_avail, quick_avail) = (precise_avail as i64, quick_avail as i64);
+        let mut claimed = self
+            .claim_where(&[
+                (
+                    "queued = 0 AND type = 1 ORDER BY id LIMIT $1",
+                    &[&precise_avail],
+                ),
+                (
+                    "queued = 0 AND type = 0 ORDER BY id LIMIT $1",
+                    &[&quick_avail],
+                ),
+            ])
+            .await?;
+
+        let quick = claimed.pop().unwrap_or_default();
+        let precise = claimed.pop().unwrap_or_default();
+        Ok((precise, quick))
+    }
+    async fn reclaim_expired_submissions(&self, limit: usize) -> Result<Vec<Submission>, DbError> {
+        Ok(self
+            .claim_where(&[(
+                "queued = 1 AND state <> 2 AND (claim_expires_at IS NULL OR claim_expires_at < now()) ORDER BY id LIMIT $1",
+                &[&(limit as i64)],
+            )])
+            .await?
+            .pop()
+            .unwrap_or_default())
+    }
+    async fn claim_submission(&self, id: i32) -> Result<Option<Submission>, DbError> {
+        let coordinator = &config::get().coordinator;
+
+        self.run("claim", |mut client| async move {
+            let tx = client.transaction().await?;
+
+            let locked = tx
+                .query_opt(
+                    r#"SELECT id FROM "Submit" WHERE id = $1 AND (state = 2 OR claimed_by IS NULL OR claimed_by = $2 OR claim_expires_at IS NULL OR claim_expires_at < now()) FOR UPDATE SKIP LOCKED"#,
+                    &[&id, &coordinator.id],
+                )
+                .await?;
+            if locked.is_none() {
+                tx.rollback().await?;
+                return Ok(None);
+            }
+
+            // 새 채점이므로 같은 트랜잭션에서 generation을 올리고 올린 값을 돌려줌
+            let row = tx
+                .query_one(
+                    r#"UPDATE "Submit" SET queued = 1, claimed_by = $2, claim_expires_at = now() + $3::int8 * interval '1 second', state = $4, generation = generation + 1 WHERE id = $1 RETURNING *"#,
+                    &[
+                        &id,
+                        &coordinator.id,
+                        &(coordinator.lease_secs as i64),
+                        &(SubmissionState::InProgress as i16),
+                    ],
+                )
+                .await?;
+
+            tx.commit().await?;
+            Ok(Some(submission_from_row(&row)?))
+        })
+        .await
+    }
+    async fn cancel_submission(&self, id: i32) -> Result<(), DbError> {
+        self.execute(
+            r#"UPDATE "Submit" SET state = 2, extra = 'cancelled', claim_expires_at = NULL WHERE id = $1 AND claimed_by = $2"#,
+            &[&id, &config::get().coordinator.id],
+        )
+        .await
+    }
+    async fn renew_claims(&self, ids: Vec<i32>) -> Result<(), DbError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let coordinator = &config::get().coordinator;
+        self.execute(
+            r#"UPDATE "Submit" SET claim_expires_at = now() + $1::int8 * interval '1 second' WHERE claimed_by = $2 AND id = ANY($3)"#,
+            &[&(coordinator.lease_secs as i64), &coordinator.id, &ids],
+        )
+        .await
+    }
+    async fn list_testcase(&self, problem_no: i32) -> Result<Vec<TestCase>, DbError> {
+        self.query(
+            r#"SELECT * FROM "Testcase" WHERE problem_id = $1"#,
+            &[&problem_no],
+        )
+        .await?
+        .iter()
+        .map(|row| Ok(testcase_from_row(row)?))
+        .collect()
+    }
+    async fn contest_window(&self) -> Result<ContestWindow, DbError> {
+        let mut window = ContestWindow::default();
+
+        let rows = self
+            .query(
+                "SELECT key, val FROM config WHERE key IN ($1, $2)",
+                &[&"START_AT", &"END_AT"],
+            )
+            .await?;
+        for row in rows {
+            let key: String = column(&row, "config", "key")?;
+            let at = match column::<Option<String>>(&row, "config", "val")? {
+                Some(val) => Some(
+                    NaiveDateTime::parse_from_str(&val, "%Y-%m-%d %H:%M:%S").map_err(|_| {
+                        RowError {
+                            table: "config",
+                            column: "val",
+                            reason: format!("invalid datetime {:?}", val),
+                        }
+                    })?,
+                ),
+                None => None,
+            };
+
+            match key.as_str() {
+                "START_AT" => window.start_at = at,
+                "END_AT" => window.end_at = at,
+                _ => (),
+            }
+        }
+
+        Ok(window)
+    }
+    async fn upsert_testcase_judge(
+        &self,
+        submission_id: i32,
+        generation: u32,
+        testcase_id: i32,
+        attempt: u32,
+        result: &TestCaseJudgeResult,
+        result_inner: &TestCaseJudgeResultInner,
+    ) -> Result<(), DbError> {
+        let run = (attempt, result.clone(), result_inner.clone());
+
+        self.run(UPSERT_TESTCASE_JUDGE, |client| {
+            let run = &run;
+            async move {
+                upsert_testcase_judge(&client, submission_id, generation, testcase_id, run).await
+            }
+        })
+        .await
+    }
+    async fn list_testcase_judge(
+        &self,
+        submission_id: i32,
+        generation: u32,
+    ) -> Result<Vec<TestCaseRun>, DbError> {
+        const TABLE: &str = "Testcase_judge";
+        let mut latest = BTreeMap::new();
+
+        let rows = self
+            .query(
+                r#"SELECT * FROM "Testcase_judge" WHERE submit_id = $1 AND generation = $2 ORDER BY id"#,
+                &[&submission_id, &(generation as i32)],
+            )
+            .await?;
+        for row in rows {
+            let attempt = column::<i32>(&row, TABLE, "attempt")? as u32;
+            let result_extra: Option<String> = column(&row, TABLE, "result_extra")?;
+            let result_inner = result_extra
+                .as_deref()
+                .and_then(|v| v.parse().ok())
+                .ok_or_else(|| RowError {
+                    table: TABLE,
+                    column: "result_extra",
+                    reason: format!("unknown verdict {:?}", result_extra),
+                })?;
+
+            let result = testcase_judge_from_row(&row)?;
+            latest.insert(
+                (result.testcase_id, attempt),
+                (attempt, result, result_inner),
+            );
+        }
+
+        Ok(latest.into_values().collect())
+    }
+    async fn finish_submission(
+        &self,
+        submission: &Submission,
+        verdict: &Verdict,
+        runs: &[TestCaseRun],
+    ) -> Result<Finished, DbError> {
+        let coordinator_id = &config::get().coordinator.id;
+
+        self.run("finish", |mut client| async move {
+            let tx = client.transaction().await?;
+
+            // lease가 만료돼서 다른 코디네이터가 가져갔거나 그 사이에 재채점된 제출이면 덮어쓰지 않음
+            let claimed_by: Option<String> = tx
+                .query_opt(
+                    r#"SELECT claimed_by FROM "Submit" WHERE id = $1 AND generation = $2 FOR UPDATE"#,
+                    &[&submission.id, &(submission.generation as i32)],
+                )
+                .await?
+                .map(|row| column(&row, "Submit", "claimed_by"))
+                .transpose()?
+                .flatten();
+            if claimed_by.as_ref() != Some(coordinator_id) {
+                return Ok(Finished::NotOwner);
+            }
+
+            // MySQL의 STR_TO_DATE/TIMESTAMPDIFF 대신 timestamp끼리 빼서 초로 바꿈
+            let row = tx
+                .query_one(
+                    r#"
+    SELECT
+        count(*) AS tries,
+        (SELECT EXTRACT(EPOCH FROM ($1::timestamp - val::timestamp))::int8 FROM config WHERE key = 'START_AT') AS sec_diff
+    FROM "Submit" WHERE stud_id = $2 AND type = 1 AND "problemNo" = $3 AND result = 0
+    AND (id < (SELECT min(id) FROM "Submit" WHERE stud_id = $2 AND type = 1 AND "problemNo" = $3 AND result = 1))
+    "#,
+                    &[&submission.submit_at, &submission.stud_id, &submission.problem_no],
+                )
+                .await?;
+            let retries = column::<i64>(&row, "Submit", "tries")?;
+            let secs = column::<Option<i64>>(&row, "Submit", "sec_diff")?.unwrap_or(0);
+            let score = if verdict.is_correct {
+                (retries.max(0) * 20 + secs.max(0) / 60) as i32
+            } else {
+                0
+            };
+
+            for run in runs {
+                upsert_testcase_judge(
+                    &tx,
+                    submission.id,
+                    submission.generation,
+                    run.1.testcase_id,
+                    run,
+                )
+                .await?;
+            }
+            tx.execute(
+                r#"UPDATE "Submit" SET score = $1, result = $2, extra = $3, memory = $4, runtime = $5, state = 2, needs_review = GREATEST(needs_review, $6), claim_expires_at = NULL WHERE id = $7 AND claimed_by = $8"#,
+                &[
+                    &score,
+                    &(if verdict.is_correct { 0i16 } else { 1 }),
+                    &verdict.extra,
+                    &(verdict.memory as i32),
+                    &(verdict.runtime as i32),
+                    &(verdict.needs_review as i16),
+                    &submission.id,
+                    coordinator_id,
+                ],
+            )
+            .await?;
+
+            tx.commit().await?;
+            Ok(Finished::Saved)
+        })
+        .await
+    }
+    async fn schema_version(&self) -> Result<u32, DbError> {
+        self.run(
+            "SELECT",
+            |client| async move { schema_version(&client).await },
+        )
+        .await
+    }
+    async fn migrate(&self) -> Result<Vec<&'static Migration>, DbError> {
+        self.run("migrate", |mut client| async move {
+            // DDL도 트랜잭션에 묶이므로 중간에 실패하면 아무것도 적용되지 않음.
+            // 동시에 migrate를 돌리면 schema_version의 primary key에서 한쪽이 실패함
+            let tx = client.transaction().await?;
+            // 이미 있는 테이블을 건너뛸 때마다 NOTICE가 오므로 끔
+            tx.batch_execute("SET LOCAL client_min_messages = warning")
+                .await?;
+            tx.batch_execute(migrate::VERSION_TABLE).await?;
+            upgrade_legacy(&tx).await?;
+            let pending = migrate::pending(migrate::POSTGRES, schema_version(&tx).await?);
+            for migration in &pending {
+                tx.batch_execute(migration.sql).await?;
+                tx.execute(
+                    "INSERT INTO schema_version (version, name) VALUES ($1, $2)",
+                    &[&(migration.version as i32), &migration.name],
+                )
+                .await?;
+            }
+            tx.commit().await?;
+
+            Ok(pending)
+        })
+        .await
+    }
+
+    async fn wait_for_submission(&self) {
+        self.listening.call_once(|| {
+            tokio::spawn(listen(self.pg_config.clone(), self.submitted.clone()));
+        });
+        self.submitted.notified().await
+    }
+}
+
+impl PostgresStore {
+    async fn query(&self, sql: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Vec<Row>, DbError> {
+        self.run(sql, |client| async move {
+            let stmt = client.prepare_cached(sql).await?;
+            Ok(client.query(&stmt, params).await?)
+        })
+        .await
+    }
+    async fn execute(&self, sql: &str, params: &[&(dyn ToSql + Sync)]) -> Result<(), DbError> {
+        self.run(sql, |client| async move {
+            let stmt = client.prepare_cached(sql).await?;
+            client.execute(&stmt, params).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    #[allow(dead_code)]
+    async fn update_user_problem_stat(
+        &self,
+        stud_id: i32,
+        problem_no: i32,
+        score: usize,
+    ) -> Result<(), DbError> {
+        self.execute(
+            "UPDATE user_problem_stat SET score = $1 WHERE stud_id = $2 AND problem_no = $3",
+            &[&(score as i32), &stud_id, &problem_no],
+        )
+        .await
+    }
+}
+
+/// (submit_id, testcase_id, attempt)가 같으면 덮어씀
+const UPSERT_TESTCASE_JUDGE: &str = r#"INSERT INTO "Testcase_judge" (submit_id, testcase_id, output, runtime, result, compile_log, memory, judge_server_id, result_extra, attempt, generation) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+    ON CONFLICT (submit_id, testcase_id, attempt) DO UPDATE SET generation = excluded.generation, output = excluded.output, runtime = excluded.runtime, result = excluded.result, compile_log = excluded.compile_log, memory = excluded.memory, judge_at = now(), judge_server_id = excluded.judge_server_id, result_extra = excluded.result_extra"#;
+
+async fn upsert_testcase_judge(
+    client: &impl GenericClient,
+    submission_id: i32,
+    generation: u32,
+    testcase_id: i32,
+    (attempt, result, result_inner): &TestCaseRun,
+) -> Result<(), DbError> {
+    let stmt = client.prepare_cached(UPSERT_TESTCASE_JUDGE).await?;
+    client
+        .execute(
+            &stmt,
+            &[
+                &submission_id,
+                &testcase_id,
+                &result.output,
+                &result.runtime.map(|v| v as i32),
+                &(if result.result { 0i16 } else { 1 }),
+                &result.compile_log.clone().unwrap_or_default(),
+                &(result.memory.unwrap_or(0) as i32),
+                &result.judge_server_id,
+                &result_inner.to_string(),
+                &(*attempt as i32),
+                &(generation as i32),
+            ],
+        )
+        .await?;
+    Ok(())
+}
+
+async fn schema_version(client: &impl GenericClient) -> Result<u32, DbError> {
+    let exists: bool = client
+        .query_one("SELECT to_regclass('schema_version') IS NOT NULL", &[])
+        .await?
+        .get(0);
+    if !exists {
+        return Ok(0);
+    }
+
+    let row = client
+        .query_one(
+            "SELECT COALESCE(MAX(version), 0) AS version FROM schema_version",
+            &[],
+        )
+        .await?;
+    Ok(column::<i32>(&row, "schema_version", "version")?.max(0) as u32)
+}
+
+/// 마이그레이션 도입 전부터 있던 테이블에 빠진 컬럼을 추가함 (`migrate::LEGACY_COLUMNS`)
+async fn upgrade_legacy(client: &impl GenericClient) -> Result<(), DbError> {
+    for column in migrate::LEGACY_COLUMNS {
+        let existing: Vec<String> = client
+            .query(
+                "SELECT column_name::text FROM information_schema.columns WHERE table_schema = current_schema() AND table_name = $1",
+                &[&column.table],
+            )
+            .await?
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        if !column.is_missing(&existing) {
+            continue;
+        }
+
+        // 이름은 바인딩할 수 없음. 코드에 있는 상수만 씀
+        client
+            .batch_execute(&format!(
+                r#"ALTER TABLE "{}" ADD COLUMN {} {}"#,
+                column.table, column.name, column.postgres
+            ))
+            .await?;
+        tracing::info!(
+            table = column.table,
+            column = column.name,
+            "added missing column"
+        );
+    }
+    Ok(())
+}
+
+/// `LISTEN judge_submit`을 유지함. 연결이 끊기면 잠시 뒤에 다시 연결함
+///
+/// 알림은 폴링을 당기는 용도일 뿐이므로 놓쳐도 다음 폴링에서 가져감
+async fn listen(pg_config: tokio_postgres::Config, submitted: Arc<Notify>) {
+    let database = &config::get().database;
+
+    loop {
+        if let Err(e) = listen_once(&pg_config, &submitted).await {
+            tracing::warn!(error = %e, "lost postgres notification connection");
+        }
+        tokio::time::sleep(database.retry_backoff(database.max_retries.max(1))).await;
+    }
+}
+async fn listen_once(
+    pg_config: &tokio_postgres::Config,
+    submitted: &Arc<Notify>,
+) -> Result<(), tokio_postgres::Error> {
+    let (client, mut connection) = pg_config.connect(NoTls).await?;
+
+    // LISTEN 응답도 connection을 돌려야 오므로 먼저 띄움
+    let notify = submitted.clone();
+    let messages = tokio::spawn(async move {
+        let mut messages = futures_util::stream::poll_fn(move |cx| connection.poll_message(cx));
+        while let Some(message) = messages.next().await {
+            if let AsyncMessage::Notification(_) = message? {
+                notify.notify_one();
+            }
+        }
+        Ok(())
+    });
+
+    client
+        .batch_execute(&format!("LISTEN {}", NOTIFY_CHANNEL))
+        .await?;
+    tracing::info!(channel = NOTIFY_CHANNEL, "listening for new submissions");
+    // 연결이 끊겨 있던 동안 들어온 제출이 있을 수 있음
+    submitted.notify_one();
+
+    let result = messages.await.unwrap_or(Ok(()));
+    drop(client);
+    result
+}
+
+fn column<T: for<'a> FromSql<'a>>(
+    row: &Row,
+    table: &'static str,
+    name: &'static str,
+) -> Result<T, RowError> {
+    row.try_get(name).map_err(|e| RowError {
+        table,
+        column: name,
+        reason: e.to_string(),
+    })
+}
+
+/// 정수 코드로 저장한 컬럼. `codes[코드]`
+fn coded<T: Copy>(
+    row: &Row,
+    table: &'static str,
+    name: &'static str,
+    codes: &[T],
+) -> Result<T, RowError> {
+    let code: i16 = column(row, table, name)?;
+
+    usize::try_from(code)
+        .ok()
+        .and_then(|i| codes.get(i))
+        .copied()
+        .ok_or_else(|| RowError {
+            table,
+            column: name,
+            reason: format!("unexpected value {}", code),
+        })
+}
+
+fn submission_from_row(row: &Row) -> Result<Submission, RowError> {
+    const TABLE: &str = "Submit";
+
+    let lang: String = column(row, TABLE, "lang")?;
+    let result = match column::<Option<i16>>(row, TABLE, "result")? {
+        Some(_) => Some(coded(
+            row,
+            TABLE,
+            "result",
+            &[SubmissionResult::Correct, SubmissionResult::Wrong],
+        )?),
+        None => None,
+    };
+
+    Ok(Submission {
+        id: column(row, TABLE, "id")?,
+        stud_id: column(row, TABLE, "stud_id")?,
+        run_type: coded(
+            row,
+            TABLE,
+            "type",
+            &[SubmissionType::Quick, SubmissionType::Precise],
+        )?,
+        problem_no: column(row, TABLE, "problemNo")?,
+        lang: SubmissionLanguage::try_from(lang.clone()).map_err(|_| RowError {
+            table: TABLE,
+            column: "lang",
+            reason: format!("unexpected value {:?}", lang),
+        })?,
+        code: column(row, TABLE, "code")?,
+        state: coded(
+            row,
+            TABLE,
+            "state",
+            &[
+                SubmissionState::Submitted,
+                SubmissionState::InProgress,
+                SubmissionState::Done,
+            ],
+        )?,
+        extra: column(row, TABLE, "extra")?,
+        result,
+        submit_at: column(row, TABLE, "submit_at")?,
+        runtime: column(row, TABLE, "runtime")?,
+        memory: column(row, TABLE, "memory")?,
+        score: column::<Option<i32>>(row, TABLE, "score")?.map(|score| score.max(0) as u32),
+        generation: column::<i32>(row, TABLE, "generation")?.max(0) as u32,
+    })
+}
+
+fn testcase_from_row(row: &Row) -> Result<TestCase, RowError> {
+    const TABLE: &str = "Testcase";
+
+    Ok(TestCase {
+        id: column(row, TABLE, "id")?,
+        input: column(row, TABLE, "input")?,
+        output: column(row, TABLE, "output")?,
+        problem_id: column(row, TABLE, "problem_id")?,
+        is_public: column(row, TABLE, "isPublic")?,
+        runtime: column::<Option<i32>>(row, TABLE, "runtime")?.map(|v| v.max(0) as usize),
+        memory_limit: column::<Option<i32>>(row, TABLE, "memory_limit")?.map(|v| v.max(0) as usize),
+        is_decimal_mode: column(row, TABLE, "is_decimal_mode")?,
+    })
+}
+
+fn testcase_judge_from_row(row: &Row) -> Result<TestCaseJudgeResult, RowError> {
+    const TABLE: &str = "Testcase_judge";
+
+    Ok(TestCaseJudgeResult {
+        id: column(row, TABLE, "id")?,
+        submit_id: column(row, TABLE, "submit_id")?,
+        testcase_id: column(row, TABLE, "testcase_id")?,
+        output: column(row, TABLE, "output")?,
+        runtime: column::<Option<i32>>(row, TABLE, "runtime")?.map(|v| v.max(0) as usize),
+        memory: column::<Option<i32>>(row, TABLE, "memory")?.map(|v| v.max(0) as usize),
+        result: column::<i16>(row, TABLE, "result")? == 0,
+        compile_log: column(row, TABLE, "compile_log")?,
+        judge_server_id: column(row, TABLE, "judge_server_id")?,
+    })
+}
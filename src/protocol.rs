@@ -205,7 +205,7 @@ impl MessageBody {
         // eprintln!("data type: {}", data_type);
         *buf_len_guard -= 4;
 
-        return match data_type {
+        match data_type {
             1 => {
                 if *buf_len_guard < 8 {
                     return Err(*buf_len_guard);
@@ -289,7 +289,7 @@ impl MessageBody {
             }
             7 => Ok(MessageData::None),
             _ => unreachable!(),
-        };
+        }
     }
 }
 
@@ -649,7 +649,7 @@ impl TryFrom<MessageBody> for MsgResult {
                     testcase_id,
                     output_compile,
                     output_run,
-                    result: result.try_into().unwrap(),
+                    result: result.into(),
                     result_extra,
                     time_used,
                     memory_used,
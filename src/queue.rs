@@ -0,0 +1,428 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::{clock::Clock, types::*};
+
+/// 작업 우선순위. 위에 있을수록(값이 작을수록) 먼저 배정됨
+///
+/// 1. `Retry`: 워커가 거절했거나 연결이 끊겨서 다시 들어온 작업. 이미 한번 기다렸으므로 가장 먼저
+/// 2. `ContestSubmission`: 대회 시간 중의 제출. 점수에 영향을 주므로 실행보다 우선
+/// 3. `Run`: "실행" 버튼. 학생이 결과를 기다리고 있음
+/// 4. `Practice`: 대회 시간 밖의 제출
+/// 5. `AdminRejudge`: 관리자 재채점. 한번에 많이 들어오므로 다른 작업을 막지 않도록 가장 나중
+///
+/// 같은 우선순위 안에서는 먼저 들어온 작업이 먼저 나감 (FIFO).
+/// 단 `TaskQueue::pop_fair`는 같은 우선순위 안에서 돌리고 있는 작업이 적은 학생을 먼저 고르고,
+/// 오래 기다린 작업의 우선순위를 올려줌 (aging)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub enum PriorityClass {
+    Retry,
+    ContestSubmission,
+    Run,
+    Practice,
+    AdminRejudge,
+}
+impl PriorityClass {
+    pub const ALL: [PriorityClass; 5] = [
+        PriorityClass::Retry,
+        PriorityClass::ContestSubmission,
+        PriorityClass::Run,
+        PriorityClass::Practice,
+        PriorityClass::AdminRejudge,
+    ];
+
+    /// 새로 들어온 제출의 우선순위
+    pub fn of(submission: &Submission, contest: &ContestWindow) -> Self {
+        match submission.run_type {
+            SubmissionType::Quick => PriorityClass::Run,
+            SubmissionType::Precise if contest.contains(&submission.submit_at) => {
+                PriorityClass::ContestSubmission
+            }
+            SubmissionType::Precise => PriorityClass::Practice,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QueuedTask {
+    pub submission: Submission,
+    pub testcase: TestCase,
+    pub class: PriorityClass,
+    /// 같은 테스트케이스를 몇 번째로 돌리는지 (교차 검증 채점에서 0, 1 그리고 판정이 갈리면 2)
+    pub attempt: u32,
+    pub enqueued_at: Instant,
+    /// 최대 대기 시간을 넘겨서 이미 보고했는지
+    is_overdue_reported: bool,
+    seq: u64,
+}
+
+type TaskKey = (Instant, u64);
+
+/// 작업 큐
+///
+/// 기다린 시간만큼 우선순위가 올라감 (aging): `aging_step`마다 한 단계씩 올라가서 결국 `Retry`와 같은 순위가 됨.
+/// `max_wait` 이상 기다린 작업은 우선순위와 학생별 제한에 관계없이 가장 먼저, 오래 기다린 순으로 배정함.
+/// 같은 순위 안에서는 들어온 시각, 들어온 순서 순 (들어온 시각이 같을 수 있어서 순서 번호로 FIFO를 보장함)
+///
+/// 우선순위마다 따로 들어온 순서대로 저장함. 한 우선순위 안에서는 앞에 있을수록 오래 기다렸으므로
+/// 순위가 높고, 꺼낼 때 각 우선순위의 앞부분만 보면 됨
+#[derive(Debug)]
+pub struct TaskQueue {
+    classes: [BTreeMap<TaskKey, QueuedTask>; PriorityClass::ALL.len()],
+    seq: u64,
+
+    clock: Arc<dyn Clock>,
+    aging_step: Duration,
+    max_wait: Duration,
+}
+impl TaskQueue {
+    pub fn new(clock: Arc<dyn Clock>, aging_step: Duration, max_wait: Duration) -> Self {
+        Self {
+            classes: Default::default(),
+            seq: 0,
+            clock,
+            aging_step,
+            max_wait,
+        }
+    }
+
+    pub fn push(
+        &mut self,
+        submission: Submission,
+        testcase: TestCase,
+        class: PriorityClass,
+        attempt: u32,
+    ) {
+        let enqueued_at = self.clock.now();
+
+        self.seq += 1;
+        self.classes[class as usize].insert(
+            (enqueued_at, self.seq),
+            QueuedTask {
+                submission,
+                testcase,
+                class,
+                attempt,
+                enqueued_at,
+                is_overdue_reported: false,
+                seq: self.seq,
+            },
+        );
+    }
+
+    /// 꺼냈지만 배정하지 못한 작업을 원래 자리에 되돌림
+    pub fn requeue(&mut self, task: QueuedTask) {
+        self.classes[task.class as usize].insert((task.enqueued_at, task.seq), task);
+    }
+
+    /// 기다린 시간을 반영한 우선순위. 작을수록 먼저
+    fn aged_level(&self, task: &QueuedTask, now: Instant) -> usize {
+        let waited = now.saturating_duration_since(task.enqueued_at);
+        let promoted = waited.as_nanos() / self.aging_step.as_nanos().max(1);
+
+        (task.class as usize).saturating_sub(promoted.try_into().unwrap_or(usize::MAX))
+    }
+
+    /// 학생별 공정 분배를 적용해서 꺼냄
+    ///
+    /// - 최대 대기 시간을 넘긴 작업은 `cap`과 관계없이 가장 먼저, 오래 기다린 순으로 꺼냄
+    /// - 그 외에는 가장 높은 (기다린 시간을 반영한) 우선순위 중에서, 지금 돌리고 있는 작업(`in_flight`)이 가장 적은 학생의 작업을 고름 (라운드 로빈).
+    ///   그래도 같으면 먼저 들어온 작업
+    /// - 이미 `cap`개 이상 돌리고 있는 학생은 건너뜀. 다른 학생이 기다리고 있지 않으면 (전부 건너뛰게 되면) 그냥 순서대로 꺼냄
+    pub fn pop_fair(&mut self, in_flight: &HashMap<i32, usize>, cap: usize) -> Option<QueuedTask> {
+        let now = self.clock.now();
+
+        // 각 우선순위의 맨 앞이 그 우선순위에서 가장 오래 기다린 작업
+        let oldest = self
+            .classes
+            .iter()
+            .filter_map(|tasks| tasks.first_key_value())
+            .min_by_key(|(key, _)| **key)
+            .map(|(_, task)| task);
+        if let Some(task) = oldest {
+            if self.is_overdue(task) {
+                let (class, key) = (task.class, (task.enqueued_at, task.seq));
+                return self.classes[class as usize].remove(&key);
+            }
+        }
+
+        // (우선순위, 돌리고 있는 작업 수, 들어온 순서)
+        let mut best: Option<(usize, usize, TaskKey, PriorityClass)> = None;
+        let mut fallback: Option<(usize, TaskKey, PriorityClass)> = None;
+
+        for (class, tasks) in PriorityClass::ALL.iter().zip(&self.classes) {
+            for (key, task) in tasks {
+                // 뒤로 갈수록 덜 기다렸으므로 순위가 같거나 낮음
+                let level = self.aged_level(task, now);
+                if best.is_some_and(|(l, ..)| level > l) {
+                    break;
+                }
+                if fallback.is_none_or(|(l, k, _)| (level, *key) < (l, k)) {
+                    fallback = Some((level, *key, *class));
+                }
+
+                let running = in_flight
+                    .get(&task.submission.stud_id)
+                    .copied()
+                    .unwrap_or(0);
+                if running >= cap {
+                    continue;
+                }
+
+                let candidate = (level, running, *key, *class);
+                if best.is_none_or(|b| candidate < b) {
+                    best = Some(candidate);
+                }
+                // 이 우선순위에서 더 나은 작업은 없음
+                if running == 0 {
+                    break;
+                }
+            }
+        }
+
+        let (class, key) = match (best, fallback) {
+            (Some((_, _, key, class)), _) => (class, key),
+            (None, Some((_, key, class))) => (class, key),
+            (None, None) => return None,
+        };
+
+        self.classes[class as usize].remove(&key)
+    }
+
+    /// 최대 대기 시간을 처음 넘긴 작업을 표시하고 복사본을 돌려줌. 작업은 큐에 그대로 남고, 한 작업은 한번만 돌려줌
+    pub fn mark_overdue(&mut self) -> Vec<QueuedTask> {
+        let now = self.clock.now();
+        let max_wait = self.max_wait;
+
+        self.classes
+            .iter_mut()
+            .flat_map(|tasks| tasks.values_mut())
+            .filter(|task| {
+                !task.is_overdue_reported
+                    && now.saturating_duration_since(task.enqueued_at) >= max_wait
+            })
+            .map(|task| {
+                task.is_overdue_reported = true;
+                task.clone()
+            })
+            .collect()
+    }
+
+    pub fn is_overdue(&self, task: &QueuedTask) -> bool {
+        self.clock.now().saturating_duration_since(task.enqueued_at) >= self.max_wait
+    }
+
+    pub fn contains(&self, submission_id: i32, testcase_id: i32, attempt: u32) -> bool {
+        self.iter().any(|t| {
+            t.submission.id == submission_id && t.testcase.id == testcase_id && t.attempt == attempt
+        })
+    }
+
+    /// 제출의 작업을 모두 뺌. 뺀 작업 수
+    pub fn remove_submission(&mut self, submission_id: i32) -> usize {
+        let mut removed = 0;
+        for tasks in &mut self.classes {
+            let len = tasks.len();
+            tasks.retain(|_, t| t.submission.id != submission_id);
+            removed += len - tasks.len();
+        }
+
+        removed
+    }
+
+    /// 큐에 들어있는 순서 (aging은 반영되지 않음)
+    pub fn iter(&self) -> impl Iterator<Item = &QueuedTask> {
+        self.classes.iter().flat_map(|tasks| tasks.values())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::VirtualClock;
+
+    const AGING_STEP: Duration = Duration::from_secs(30);
+    const MAX_WAIT: Duration = Duration::from_secs(300);
+
+    fn queue() -> (Arc<VirtualClock>, TaskQueue) {
+        let clock = Arc::new(VirtualClock::new());
+        let queue = TaskQueue::new(clock.clone(), AGING_STEP, MAX_WAIT);
+        (clock, queue)
+    }
+
+    fn push(queue: &mut TaskQueue, id: i32, stud_id: i32, class: PriorityClass) {
+        let submission = Submission {
+            id,
+            stud_id,
+            run_type: SubmissionType::Precise,
+            problem_no: 1,
+            lang: SubmissionLanguage::C,
+            code: String::new(),
+            state: SubmissionState::Submitted,
+            extra: None,
+            result: None,
+            submit_at: chrono::NaiveDateTime::default(),
+            runtime: None,
+            memory: None,
+            score: None,
+            generation: 0,
+        };
+        let testcase = TestCase {
+            id: 1,
+            input: String::new(),
+            output: String::new(),
+            problem_id: 1,
+            is_public: false,
+            runtime: None,
+            memory_limit: None,
+            is_decimal_mode: 0,
+        };
+        queue.push(submission, testcase, class, 0);
+    }
+
+    /// 꺼낸 순서대로 제출 id
+    fn drain(queue: &mut TaskQueue, in_flight: &HashMap<i32, usize>, cap: usize) -> Vec<i32> {
+        std::iter::from_fn(|| queue.pop_fair(in_flight, cap))
+            .map(|task| task.submission.id)
+            .collect()
+    }
+
+    #[test]
+    fn class_order() {
+        let (_, mut queue) = queue();
+        push(&mut queue, 5, 5, PriorityClass::AdminRejudge);
+        push(&mut queue, 4, 4, PriorityClass::Practice);
+        push(&mut queue, 3, 3, PriorityClass::Run);
+        push(&mut queue, 2, 2, PriorityClass::ContestSubmission);
+        push(&mut queue, 1, 1, PriorityClass::Retry);
+
+        assert_eq!(drain(&mut queue, &HashMap::new(), 1), [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn fifo_within_class() {
+        let (clock, mut queue) = queue();
+        push(&mut queue, 1, 1, PriorityClass::Practice);
+        push(&mut queue, 2, 2, PriorityClass::Practice);
+        clock.set(Duration::from_secs(1));
+        push(&mut queue, 3, 3, PriorityClass::Practice);
+        push(&mut queue, 4, 4, PriorityClass::Practice);
+
+        // 들어온 시각이 같아도 들어온 순서대로
+        assert_eq!(drain(&mut queue, &HashMap::new(), 1), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn requeue_keeps_position() {
+        let (clock, mut queue) = queue();
+        push(&mut queue, 1, 1, PriorityClass::Practice);
+        clock.set(Duration::from_secs(1));
+        push(&mut queue, 2, 2, PriorityClass::Practice);
+
+        let task = queue.pop_fair(&HashMap::new(), 1).unwrap();
+        assert_eq!(task.submission.id, 1);
+        queue.requeue(task);
+        assert_eq!(drain(&mut queue, &HashMap::new(), 1), [1, 2]);
+    }
+
+    #[test]
+    fn student_cap() {
+        let (_, mut queue) = queue();
+        push(&mut queue, 1, 1, PriorityClass::Practice);
+        push(&mut queue, 2, 1, PriorityClass::Practice);
+        push(&mut queue, 3, 2, PriorityClass::Practice);
+        push(&mut queue, 4, 3, PriorityClass::AdminRejudge);
+
+        // 학생 1은 이미 제한만큼 돌리고 있으므로 우선순위가 낮아도 다른 학생 작업이 먼저
+        let in_flight = HashMap::from([(1, 2)]);
+        assert_eq!(queue.pop_fair(&in_flight, 2).unwrap().submission.id, 3);
+        assert_eq!(queue.pop_fair(&in_flight, 2).unwrap().submission.id, 4);
+        // 다른 학생이 기다리고 있지 않으면 제한을 넘어도 순서대로
+        assert_eq!(drain(&mut queue, &in_flight, 2), [1, 2]);
+    }
+
+    #[test]
+    fn fewest_in_flight_first() {
+        let (_, mut queue) = queue();
+        push(&mut queue, 1, 1, PriorityClass::Practice);
+        push(&mut queue, 2, 2, PriorityClass::Practice);
+        push(&mut queue, 3, 3, PriorityClass::Practice);
+
+        // 같은 우선순위 안에서는 돌리고 있는 작업이 적은 학생부터
+        let in_flight = HashMap::from([(1, 2), (2, 1)]);
+        assert_eq!(drain(&mut queue, &in_flight, 4), [3, 2, 1]);
+    }
+
+    #[test]
+    fn remove_submission() {
+        let (_, mut queue) = queue();
+        push(&mut queue, 1, 1, PriorityClass::Practice);
+        push(&mut queue, 1, 1, PriorityClass::Retry);
+        push(&mut queue, 2, 2, PriorityClass::Practice);
+
+        assert!(queue.contains(1, 1, 0));
+        assert_eq!(queue.remove_submission(1), 2);
+        assert!(!queue.contains(1, 1, 0));
+        assert_eq!(drain(&mut queue, &HashMap::new(), 1), [2]);
+    }
+
+    #[test]
+    fn aging_promotes_one_class_per_step() {
+        let (clock, mut queue) = queue();
+        push(&mut queue, 1, 1, PriorityClass::AdminRejudge);
+        clock.set(AGING_STEP * 2);
+        push(&mut queue, 2, 2, PriorityClass::Practice);
+        push(&mut queue, 3, 3, PriorityClass::Run);
+
+        // 재채점은 두 단계 올라가서 실행과 같은 순위. 같은 순위면 먼저 들어온 순
+        assert_eq!(drain(&mut queue, &HashMap::new(), 1), [1, 3, 2]);
+    }
+
+    #[test]
+    fn aging_stops_at_retry() {
+        let (clock, mut queue) = queue();
+        push(&mut queue, 1, 1, PriorityClass::Practice);
+        clock.set(AGING_STEP * 3);
+        push(&mut queue, 2, 2, PriorityClass::Retry);
+
+        // 세 단계 올라가서 Retry와 같은 순위가 됨. 그 이상은 올라가지 않음
+        assert_eq!(drain(&mut queue, &HashMap::new(), 1), [1, 2]);
+
+        let (clock, mut queue) = self::queue();
+        push(&mut queue, 1, 1, PriorityClass::Practice);
+        clock.set(AGING_STEP * 2);
+        push(&mut queue, 2, 2, PriorityClass::Retry);
+
+        assert_eq!(drain(&mut queue, &HashMap::new(), 1), [2, 1]);
+    }
+
+    #[test]
+    fn overdue_ignores_class_and_cap() {
+        let (clock, mut queue) = queue();
+        push(&mut queue, 1, 1, PriorityClass::AdminRejudge);
+        clock.set(Duration::from_secs(10));
+        push(&mut queue, 2, 2, PriorityClass::Practice);
+        clock.set(MAX_WAIT + Duration::from_secs(10));
+        push(&mut queue, 3, 3, PriorityClass::Retry);
+
+        // 둘 다 최대 대기 시간을 넘김. 우선순위나 학생별 제한과 관계없이 오래 기다린 순
+        let in_flight = HashMap::from([(1, 5), (2, 5)]);
+        assert_eq!(drain(&mut queue, &in_flight, 1), [1, 2, 3]);
+    }
+
+    #[test]
+    fn overdue_is_reported_once() {
+        let (clock, mut queue) = queue();
+        push(&mut queue, 1, 1, PriorityClass::Practice);
+        assert!(queue.mark_overdue().is_empty());
+
+        clock.set(MAX_WAIT);
+        let overdue = queue.mark_overdue();
+        assert_eq!(overdue.len(), 1);
+        assert!(queue.is_overdue(&overdue[0]));
+        assert!(queue.mark_overdue().is_empty());
+    }
+}
@@ -0,0 +1,783 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+use crate::{
+    admin::{AdminRequest, IntakeView, QueuesView, TaskView, WorkerView},
+    clock::Clock,
+    config,
+    events::EventTX,
+    metrics,
+    queue::{PriorityClass, QueuedTask},
+    store::JudgeStore,
+    task_manager::TaskManager,
+    webhook::WebhookTX,
+    ChannelMessage, TX,
+};
+
+struct Channel {
+    channel_id: usize,
+    tx: TX,
+    is_working: bool,
+    is_draining: bool,
+    is_precise_measurement: bool,
+    /// 배정한 작업. 재시도할 때 attempt 등을 그대로 유지하기 위해 들고 있음
+    current_task: Option<QueuedTask>,
+    speed_factor: f64,
+    calibrated_at: Option<Instant>,
+}
+
+/// 워커 관리와 작업 배정
+///
+/// 타이머와 메시지 수신은 호출하는 쪽이 맡음 (`main_async`는 tokio 타이머, 시뮬레이션은 가상 시계).
+/// 시간은 `Clock`, 워커 셔플은 `rng`에서만 가져오므로 같은 입력이면 같은 순서로 배정함
+pub struct Scheduler<S: JudgeStore> {
+    clock: Arc<dyn Clock>,
+    store: Arc<S>,
+    rng: StdRng,
+
+    channels: Vec<Channel>,
+    next_channel_id: usize,
+    task_manager: TaskManager<S>,
+
+    /// 관리자가 멈추면 새 제출과 lease가 만료된 제출을 가져오지 않음 (이미 가져온 제출은 계속 채점함)
+    is_intake_paused: bool,
+    poll_interval: Duration,
+}
+impl<S: JudgeStore> Scheduler<S> {
+    pub fn new(
+        clock: Arc<dyn Clock>,
+        store: Arc<S>,
+        rng: StdRng,
+        events: EventTX,
+        webhooks: WebhookTX,
+    ) -> Self {
+        let task_manager = TaskManager::new(clock.clone(), store.clone(), events, webhooks);
+
+        Self {
+            clock,
+            store,
+            rng,
+            channels: vec![],
+            next_channel_id: 1,
+            task_manager,
+            is_intake_paused: false,
+            poll_interval: config::get().scheduler.poll_interval_min(),
+        }
+    }
+
+    pub fn is_intake_paused(&self) -> bool {
+        self.is_intake_paused
+    }
+
+    /// 워커, 관리자 API, 콘솔에서 온 메시지를 처리함
+    pub async fn handle(&mut self, msg: ChannelMessage) {
+        // 워커가 비거나 큐에 작업이 다시 들어간 경우 바로 배정함 (다음 폴링까지 기다리지 않음)
+        let should_dispatch = match msg {
+            ChannelMessage::NewChannel(tx, is_precise_measurement) => {
+                let channel_id = self.next_channel_id;
+                tx.send(ChannelMessage::SetChannelId(channel_id))
+                    .await
+                    .unwrap();
+                tracing::info!(
+                    channel_id,
+                    is_precise = is_precise_measurement,
+                    "channel registered"
+                );
+
+                // 속도를 재기 전까지는 작업을 배정하지 않음
+                let is_calibrating = config::get().calibration.enabled;
+                if is_calibrating {
+                    drop(tx.send(ChannelMessage::Calibrate).await);
+                }
+
+                self.channels.push(Channel {
+                    channel_id,
+                    tx,
+                    is_working: is_calibrating,
+                    is_draining: false,
+                    is_precise_measurement,
+                    current_task: None,
+                    speed_factor: 1.0,
+                    calibrated_at: None,
+                });
+
+                self.next_channel_id += 1;
+                !is_calibrating
+            }
+            ChannelMessage::WorkDone(channel_id, submission, _testcase, result, result_inner) => {
+                if let Some(channel) = self
+                    .channels
+                    .iter_mut()
+                    .find(|channel| channel.channel_id == channel_id)
+                {
+                    channel.is_working = false;
+                    // 재채점 전에 배정한 작업이면 결과를 버리도록 배정할 때의 generation을 넘김
+                    let (attempt, generation) = channel
+                        .current_task
+                        .take()
+                        .map(|task| (task.attempt, task.submission.generation))
+                        .unwrap_or((0, submission.generation));
+
+                    tracing::info!(
+                        submission_id = submission.id,
+                        testcase_id = result.testcase_id,
+                        channel_id,
+                        attempt,
+                        verdict = ?result_inner,
+                        runtime = result.runtime,
+                        "testcase judged"
+                    );
+                    metrics::get().observe_result(submission.lang, &result, &result_inner);
+                    self.task_manager
+                        .add_result(
+                            submission.id,
+                            generation,
+                            channel_id,
+                            attempt,
+                            result,
+                            result_inner,
+                        )
+                        .await;
+                }
+                true
+            }
+            ChannelMessage::Shutdown(channel_id, submission, testcase) => {
+                if let Some(pos) = self
+                    .channels
+                    .iter()
+                    .position(|channel| channel.channel_id == channel_id)
+                {
+                    let channel = self.channels.remove(pos);
+                    tracing::info!(
+                        channel_id,
+                        is_draining = channel.is_draining,
+                        submission_id =
+                            channel.current_task.as_ref().map(|task| task.submission.id),
+                        "channel disconnected"
+                    );
+
+                    match (channel.current_task, submission, testcase) {
+                        (Some(task), _, _) => {
+                            metrics::get().count_rejudge("worker_disconnected");
+                            self.task_manager.force_rejudge(
+                                task.submission,
+                                task.testcase,
+                                PriorityClass::Retry,
+                                task.attempt,
+                            );
+                            true
+                        }
+                        (None, Some(submission), Some(testcase)) => {
+                            metrics::get().count_rejudge("worker_disconnected");
+                            self.task_manager.force_rejudge(
+                                submission,
+                                testcase,
+                                PriorityClass::Retry,
+                                0,
+                            );
+                            true
+                        }
+                        _ => false,
+                    }
+                } else {
+                    false
+                }
+            }
+            ChannelMessage::Refuse(channel_id, submission, testcase) => {
+                // 배정할 때의 attempt를 유지함. 워커는 다른 작업 중이므로 is_working은 그대로 둠
+                let task = self
+                    .channels
+                    .iter_mut()
+                    .find(|channel| channel.channel_id == channel_id)
+                    .and_then(|channel| {
+                        channel.current_task.take_if(|task| {
+                            task.submission.id == submission.id && task.testcase.id == testcase.id
+                        })
+                    });
+
+                let attempt = task.map(|task| task.attempt).unwrap_or(0);
+                tracing::warn!(
+                    submission_id = submission.id,
+                    testcase_id = testcase.id,
+                    channel_id,
+                    attempt,
+                    "worker refused task"
+                );
+                metrics::get().count_rejudge("refused");
+                self.task_manager.force_rejudge(
+                    submission,
+                    testcase,
+                    PriorityClass::Retry,
+                    attempt,
+                );
+                true
+            }
+            ChannelMessage::Expired(channel_id, submission, testcase, reason) => {
+                // 바로 뒤에 Shutdown이 오므로 채널은 거기서 정리함
+                let task = self
+                    .channels
+                    .iter_mut()
+                    .find(|channel| channel.channel_id == channel_id)
+                    .and_then(|channel| {
+                        channel.current_task.take_if(|task| {
+                            task.submission.id == submission.id && task.testcase.id == testcase.id
+                        })
+                    });
+
+                let attempt = task.map(|task| task.attempt).unwrap_or(0);
+                self.task_manager
+                    .record_requeue(submission.id, testcase.id, attempt, reason);
+                metrics::get().count_rejudge("watchdog");
+                self.task_manager.force_rejudge(
+                    submission,
+                    testcase,
+                    PriorityClass::Retry,
+                    attempt,
+                );
+                true
+            }
+            ChannelMessage::ReJudge(submission, testcase) => {
+                metrics::get().count_rejudge("admin_testcase");
+                self.task_manager.force_rejudge(
+                    submission,
+                    testcase,
+                    PriorityClass::AdminRejudge,
+                    0,
+                );
+                true
+            }
+            ChannelMessage::Calibrated(channel_id, speed_factor) => {
+                let now = self.clock.now();
+                if let Some(channel) = self
+                    .channels
+                    .iter_mut()
+                    .find(|channel| channel.channel_id == channel_id)
+                {
+                    channel.is_working = false;
+                    channel.speed_factor = speed_factor;
+                    channel.calibrated_at = Some(now);
+                    tracing::info!(channel_id, speed_factor, "channel calibrated");
+                }
+                true
+            }
+            ChannelMessage::Drain(channel_id) => {
+                if !self.drain(channel_id).await {
+                    tracing::warn!(channel_id, "drain: channel not found");
+                }
+                false
+            }
+            ChannelMessage::Admin(request) => self.handle_admin(request).await,
+            _ => false,
+        };
+
+        if should_dispatch {
+            self.dispatch().await;
+        }
+    }
+
+    /// lease 갱신 주기마다
+    pub async fn renew_leases(&mut self) {
+        if let Err(e) = self
+            .store
+            .renew_claims(self.task_manager.submission_ids())
+            .await
+        {
+            tracing::error!(error = %e, "error while renewing leases");
+        }
+    }
+
+    /// lease 만료 확인 주기마다. 입수가 멈춰 있으면 호출하지 않음
+    pub async fn reap(&mut self) {
+        let recovered = match self
+            .store
+            .reclaim_expired_submissions(config::get().coordinator.reap_batch)
+            .await
+        {
+            Ok(recovered) => recovered,
+            Err(e) => {
+                tracing::error!(error = %e, "error while reclaiming submissions");
+                return;
+            }
+        };
+        if recovered.is_empty() {
+            return;
+        }
+
+        self.refresh_contest_window().await;
+        for submission in recovered {
+            let submission_id = submission.id;
+            // lease 갱신이 늦어서 만료됐지만 아직 채점 중인 제출. 다시 가져오면서 lease가 갱신됐으므로 그대로 둠
+            if self
+                .task_manager
+                .is_current(submission_id, submission.generation)
+            {
+                tracing::warn!(
+                    submission_id,
+                    "lease expired while judging, keeping the current run"
+                );
+                continue;
+            }
+            if let Err(e) = self.task_manager.add_recovered_submission(submission).await {
+                tracing::error!(submission_id, error = %e, "cannot recover submission");
+            }
+        }
+        self.dispatch().await;
+    }
+
+    /// 폴링 타이머가 울릴 때마다. 다음 폴링까지 기다릴 시간을 반환
+    pub async fn poll(&mut self) -> Duration {
+        let scheduler_config = &config::get().scheduler;
+        self.recalibrate().await;
+
+        let fetched = if self.is_intake_paused {
+            0
+        } else {
+            self.fetch_submissions().await
+        };
+
+        // 새 제출이 있으면 바로 다시 확인하고, 없으면 점점 천천히 확인함
+        self.poll_interval = if fetched > 0 {
+            scheduler_config.poll_interval_min()
+        } else {
+            (self.poll_interval * 2).min(scheduler_config.poll_interval_max())
+        };
+
+        let now = self.clock.now();
+        let task_manager = &mut self.task_manager;
+        for (name, queue) in [
+            ("precise", &mut task_manager.task_precise),
+            ("quick", &mut task_manager.task_quick),
+        ] {
+            for task in queue.mark_overdue() {
+                tracing::warn!(
+                    submission_id = task.submission.id,
+                    testcase_id = task.testcase.id,
+                    queue = name,
+                    class = ?task.class,
+                    waited_secs = now.saturating_duration_since(task.enqueued_at).as_secs(),
+                    "queue wait exceeded"
+                );
+                metrics::get().count_queue_wait_exceeded(name, task.class);
+            }
+        }
+
+        self.dispatch().await;
+        self.poll_interval
+    }
+
+    /// 더 이상 작업이 배정되지 않도록 먼저 표시하고, 워커 쪽에는 현재 작업이 끝나면 종료하라고 알림. 없는 채널이면 `false`
+    async fn drain(&mut self, channel_id: usize) -> bool {
+        let Some(channel) = self
+            .channels
+            .iter_mut()
+            .find(|channel| channel.channel_id == channel_id)
+        else {
+            return false;
+        };
+
+        channel.is_draining = true;
+        tracing::info!(
+            channel_id,
+            submission_id = channel.current_task.as_ref().map(|task| task.submission.id),
+            "channel draining"
+        );
+
+        drop(channel.tx.send(ChannelMessage::Drain(channel_id)).await);
+        true
+    }
+
+    /// 관리자 API 요청을 처리하고 응답을 보냄. 큐가 바뀌었으면 `true`
+    async fn handle_admin(&mut self, request: AdminRequest) -> bool {
+        // 응답을 기다리던 쪽이 끊겨도 요청은 처리된 것이므로 전송 실패는 무시함
+        match request {
+            AdminRequest::Workers(reply) => {
+                let now = self.clock.now();
+                let workers = self
+                    .channels
+                    .iter()
+                    .map(|channel| WorkerView {
+                        channel_id: channel.channel_id,
+                        is_precise: channel.is_precise_measurement,
+                        is_working: channel.is_working,
+                        is_draining: channel.is_draining,
+                        speed_factor: channel.speed_factor,
+                        calibrated_secs_ago: channel
+                            .calibrated_at
+                            .map(|at| now.saturating_duration_since(at).as_secs()),
+                        current_task: channel
+                            .current_task
+                            .as_ref()
+                            .map(|task| TaskView::new(task, false, now)),
+                    })
+                    .collect();
+                let _ = reply.send(workers);
+                false
+            }
+            AdminRequest::Queues(reply) => {
+                let now = self.clock.now();
+                let _ = reply.send(QueuesView {
+                    precise: self
+                        .task_manager
+                        .task_precise
+                        .iter()
+                        .map(|task| {
+                            let is_overdue = self.task_manager.task_precise.is_overdue(task);
+                            TaskView::new(task, is_overdue, now)
+                        })
+                        .collect(),
+                    quick: self
+                        .task_manager
+                        .task_quick
+                        .iter()
+                        .map(|task| {
+                            let is_overdue = self.task_manager.task_quick.is_overdue(task);
+                            TaskView::new(task, is_overdue, now)
+                        })
+                        .collect(),
+                });
+                false
+            }
+            AdminRequest::Submissions(reply) => {
+                let _ = reply.send(self.task_manager.submission_views());
+                false
+            }
+            AdminRequest::Rejudge(id, reply) => {
+                let submission = match self.store.claim_submission(id).await {
+                    Ok(Some(submission)) => submission,
+                    Ok(None) => {
+                        let _ = reply.send(Err(format!(
+                            "submission {} not found or claimed by another coordinator",
+                            id
+                        )));
+                        return false;
+                    }
+                    Err(e) => {
+                        tracing::error!(submission_id = id, error = %e, "admin: cannot claim submission");
+                        let _ = reply.send(Err(e.to_string()));
+                        return false;
+                    }
+                };
+
+                tracing::info!(submission_id = id, "admin: rejudge submission");
+                self.refresh_contest_window().await;
+                if let Err(e) = self.task_manager.add_rejudge(submission).await {
+                    tracing::error!(submission_id = id, error = %e, "admin: cannot rejudge submission");
+                    let _ = reply.send(Err(e.to_string()));
+                    return false;
+                }
+                let _ = reply.send(Ok(()));
+                true
+            }
+            AdminRequest::Cancel(id, reply) => {
+                let is_cancelled = self.task_manager.cancel(id);
+                if is_cancelled {
+                    tracing::info!(submission_id = id, "admin: cancel submission");
+                    if let Err(e) = self.store.cancel_submission(id).await {
+                        tracing::error!(submission_id = id, error = %e, "admin: cannot mark submission cancelled");
+                    }
+                }
+                let _ = reply.send(is_cancelled);
+                false
+            }
+            AdminRequest::Drain(channel_id, reply) => {
+                let _ = reply.send(self.drain(channel_id).await);
+                false
+            }
+            AdminRequest::PauseIntake(is_paused, reply) => {
+                if self.is_intake_paused != is_paused {
+                    tracing::info!(is_paused, "admin: intake changed");
+                }
+                self.is_intake_paused = is_paused;
+                let _ = reply.send(IntakeView { is_paused });
+                false
+            }
+            AdminRequest::Intake(reply) => {
+                let _ = reply.send(IntakeView {
+                    is_paused: self.is_intake_paused,
+                });
+                false
+            }
+        }
+    }
+
+    /// 마지막으로 속도를 잰 지 오래된 워커 중 놀고 있는 워커의 속도를 다시 잼
+    async fn recalibrate(&mut self) {
+        let calibration = &config::get().calibration;
+        if !calibration.enabled {
+            return;
+        }
+
+        let now = self.clock.now();
+        for channel in self
+            .channels
+            .iter_mut()
+            .filter(|channel| !channel.is_working && !channel.is_draining)
+        {
+            if channel
+                .calibrated_at
+                .is_some_and(|at| now.saturating_duration_since(at) >= calibration.interval())
+            {
+                channel.is_working = true;
+                drop(channel.tx.send(ChannelMessage::Calibrate).await);
+            }
+        }
+    }
+
+    /// 놀고 있는 워커 수만큼 새 제출을 가져옴. 가져온 제출 수를 반환
+    async fn fetch_submissions(&mut self) -> usize {
+        // early-return 상황이 있을수 있어서 우선 검사
+        self.task_manager.process().await;
+
+        let (available_precise, available_quick): (Vec<_>, Vec<_>) = self
+            .channels
+            .iter()
+            .filter(|channel| !channel.is_working && !channel.is_draining)
+            .partition(|channel| channel.is_precise_measurement);
+        if available_precise.is_empty() && available_quick.is_empty() {
+            return 0;
+        }
+
+        // starving이 생기지 않도록 두개 다 들고오는 처리
+        let batch_min = config::get().scheduler.fetch_batch_min;
+        let (task_precise, task_quick) = match self
+            .store
+            .claim_submissions(
+                available_precise.len().max(batch_min),
+                available_quick.len().max(batch_min),
+            )
+            .await
+        {
+            Ok(claimed) => claimed,
+            Err(e) => {
+                tracing::error!(error = %e, "error while claiming submissions");
+                return 0;
+            }
+        };
+        let fetched = task_precise.len() + task_quick.len();
+        if fetched > 0 {
+            self.refresh_contest_window().await;
+
+            // 테스트케이스를 못 읽은 제출은 lease가 만료되면 reaper가 다시 가져감
+            for task in task_precise.into_iter().chain(task_quick) {
+                let submission_id = task.id;
+                if let Err(e) = self.task_manager.add_submissions(task).await {
+                    tracing::error!(submission_id, error = %e, "cannot add submission");
+                }
+            }
+        }
+
+        fetched
+    }
+
+    /// 대회 시간을 다시 읽음. 실패하면 이전 값을 그대로 씀
+    async fn refresh_contest_window(&mut self) {
+        match self.store.contest_window().await {
+            Ok(contest) => self.task_manager.contest = contest,
+            Err(e) => tracing::warn!(error = %e, "cannot read contest window, keeping previous"),
+        }
+    }
+
+    /// 큐에 있는 테스트케이스를 놀고 있는 워커에 배정함
+    async fn dispatch(&mut self) {
+        let task_manager = &mut self.task_manager;
+        let now = self.clock.now();
+
+        // Testcase는 상황에 따라서 실시간 수정 될 수도 있음. 그렇기 때문에, 루프 안에서만 캐싱 되도록 함
+        task_manager.process().await;
+
+        // 한 학생이 워커를 독차지하지 않도록 학생별로 돌리고 있는 작업 수를 셈
+        let mut in_flight: HashMap<i32, usize> = HashMap::new();
+        for task in self
+            .channels
+            .iter()
+            .filter_map(|channel| channel.current_task.as_ref())
+        {
+            *in_flight.entry(task.submission.stud_id).or_default() += 1;
+        }
+        let live_channels = self
+            .channels
+            .iter()
+            .filter(|channel| !channel.is_draining)
+            .count();
+        let student_cap =
+            ((live_channels as f64 * config::get().scheduler.max_student_share).ceil() as usize)
+                .max(1);
+
+        // 교차 검증에서 제외할 워커를 빼도 남는 정밀 채점 워커가 있는지 보기 위함
+        let live_precise: Vec<usize> = self
+            .channels
+            .iter()
+            .filter(|channel| channel.is_precise_measurement && !channel.is_draining)
+            .map(|channel| channel.channel_id)
+            .collect();
+
+        let (mut available_precise, mut available_quick): (Vec<_>, Vec<_>) = self
+            .channels
+            .iter_mut()
+            .filter(|channel| !channel.is_working && !channel.is_draining)
+            .partition(|channel| channel.is_precise_measurement);
+        if available_precise.is_empty() && available_quick.is_empty() {
+            return;
+        }
+
+        // 특정 서버에서만 (id가 낮은 서버) 작동되지 않도록 - 모든 서버에서 작동되도록 셔플
+        available_precise.shuffle(&mut self.rng);
+        available_quick.shuffle(&mut self.rng);
+
+        let mut redo = Vec::with_capacity(8);
+        let mut deferred = Vec::new();
+
+        // 배정할 워커가 있을 때만 큐에서 꺼냄 (자주 호출되므로 꺼냈다가 다시 넣는 일이 없도록)
+        while !available_precise.is_empty() {
+            let Some(task) = task_manager.task_precise.pop_fair(&in_flight, student_cap) else {
+                break;
+            };
+            if task_manager.task_precise.is_overdue(&task) {
+                tracing::warn!(
+                    submission_id = task.submission.id,
+                    testcase_id = task.testcase.id,
+                    waited_secs = now.saturating_duration_since(task.enqueued_at).as_secs(),
+                    "dispatching overdue task"
+                );
+            }
+
+            // 교차 검증 중인 테스트케이스는 이미 결과를 낸 워커에 다시 배정하지 않음.
+            // 다른 워커가 일하는 중이면 기다리고, 아예 없으면 아무 워커에나 배정하고 확인 필요로 표시함
+            let excluded = task_manager.excluded_channels(&task);
+            let pos = match available_precise
+                .iter()
+                .position(|channel| !excluded.contains(&channel.channel_id))
+            {
+                Some(pos) => pos,
+                None if live_precise.iter().any(|id| !excluded.contains(id)) => {
+                    deferred.push(task);
+                    continue;
+                }
+                None => {
+                    task_manager.record_unverified_dispatch(&task, available_precise[0].channel_id);
+                    0
+                }
+            };
+            let channel = available_precise.swap_remove(pos);
+
+            if let Err(task) = start_work(channel, task, &mut in_flight, now).await {
+                redo.push(task);
+            }
+        }
+        while !(available_quick.is_empty() && available_precise.is_empty()) {
+            let Some(task) = task_manager.task_quick.pop_fair(&in_flight, student_cap) else {
+                break;
+            };
+            if task_manager.task_quick.is_overdue(&task) {
+                tracing::warn!(
+                    submission_id = task.submission.id,
+                    testcase_id = task.testcase.id,
+                    waited_secs = now.saturating_duration_since(task.enqueued_at).as_secs(),
+                    "dispatching overdue task"
+                );
+            }
+            let channel = available_quick
+                .pop()
+                .or_else(|| available_precise.pop())
+                .unwrap();
+
+            if let Err(task) = start_work(channel, task, &mut in_flight, now).await {
+                redo.push(task);
+            }
+        }
+
+        deferred
+            .drain(..)
+            .for_each(|task| task_manager.task_precise.requeue(task));
+        redo.drain(..).for_each(|task| {
+            task_manager.force_rejudge(
+                task.submission,
+                task.testcase,
+                PriorityClass::Retry,
+                task.attempt,
+            )
+        });
+
+        self.update_gauges();
+    }
+
+    /// 큐와 워커 상태는 바뀔 때마다 dispatch를 거치므로 여기서 지표를 갱신함
+    fn update_gauges(&self) {
+        let metrics = metrics::get();
+
+        for (name, queue) in [
+            ("precise", &self.task_manager.task_precise),
+            ("quick", &self.task_manager.task_quick),
+        ] {
+            for class in PriorityClass::ALL {
+                let depth = queue.iter().filter(|task| task.class == class).count();
+                metrics
+                    .queue_depth
+                    .with_label_values(&[name, &format!("{:?}", class)])
+                    .set(depth as i64);
+            }
+        }
+
+        let draining = self
+            .channels
+            .iter()
+            .filter(|channel| channel.is_draining)
+            .count();
+        let working = self
+            .channels
+            .iter()
+            .filter(|channel| !channel.is_draining && channel.is_working)
+            .count();
+        metrics
+            .workers
+            .with_label_values(&["draining"])
+            .set(draining as i64);
+        metrics
+            .workers
+            .with_label_values(&["working"])
+            .set(working as i64);
+        metrics
+            .workers
+            .with_label_values(&["idle"])
+            .set((self.channels.len() - draining - working) as i64);
+    }
+}
+
+/// 워커에 작업을 보냄. 보내지 못하면 작업을 돌려줌
+async fn start_work(
+    channel: &mut Channel,
+    task: QueuedTask,
+    in_flight: &mut HashMap<i32, usize>,
+    now: Instant,
+) -> Result<(), QueuedTask> {
+    channel.is_working = true;
+    metrics::get().observe_dispatch(task.class, now.saturating_duration_since(task.enqueued_at));
+    tracing::debug!(
+        submission_id = task.submission.id,
+        testcase_id = task.testcase.id,
+        channel_id = channel.channel_id,
+        attempt = task.attempt,
+        class = ?task.class,
+        "task dispatched"
+    );
+    *in_flight.entry(task.submission.stud_id).or_default() += 1;
+
+    if (channel
+        .tx
+        .send(ChannelMessage::WorkStart(
+            task.submission.clone(),
+            task.testcase.clone(),
+        ))
+        .await)
+        .is_err()
+    {
+        return Err(task);
+    }
+
+    channel.current_task = Some(task);
+    Ok(())
+}
@@ -0,0 +1,767 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::Deserialize;
+use tokio::sync::oneshot;
+
+use crate::{
+    admin::AdminRequest,
+    clock::VirtualClock,
+    config::{self, Config},
+    logging,
+    scheduler::Scheduler,
+    store::MemoryStore,
+    types::*,
+    webhook, ChannelMessage, RX,
+};
+
+/// 시나리오의 판정 이름. 출력할 때도 같은 이름을 씀
+const VERDICTS: [(&str, TestCaseJudgeResultInner); 7] = [
+    ("accepted", TestCaseJudgeResultInner::Accepted),
+    ("wrong_answer", TestCaseJudgeResultInner::WrongAnswer),
+    (
+        "time_limit_exceeded",
+        TestCaseJudgeResultInner::TimeLimitExceeded,
+    ),
+    (
+        "memory_limit_exceeded",
+        TestCaseJudgeResultInner::MemoryLimitExceeded,
+    ),
+    (
+        "output_limit_exceeded",
+        TestCaseJudgeResultInner::OutputLimitExceeded,
+    ),
+    ("compile_failed", TestCaseJudgeResultInner::CompileFailed),
+    ("runtime_error", TestCaseJudgeResultInner::RuntimeError),
+];
+
+const CONTEST_START_AT: &str = "2024-03-02 09:00:00";
+const CONTEST_END_AT: &str = "2024-03-02 12:00:00";
+const CONTEST_SUBMIT_AT: &str = "2024-03-02 10:00:00";
+const PRACTICE_SUBMIT_AT: &str = "2024-03-01 10:00:00";
+
+/// 스케줄러 시뮬레이션 시나리오 (TOML)
+///
+/// DB와 워커 대신 메모리 저장소와 가짜 워커를 쓰고, 시간은 `advance`로만 흐름.
+/// 같은 시나리오와 `seed`면 항상 같은 순서로 배정되므로, `expect`로 배정 결과를 그대로 확인할 수 있음.
+/// 예시는 `scenarios/` 참고. `cargo test`에서 모두 돌림 (`tests/scenarios.rs`)
+///
+/// - `config`: 코디네이터 설정 (`judge.toml`과 같은 형식). 환경변수는 반영하지 않음
+/// - `problems`: 문제별 테스트케이스 수. 테스트케이스 id는 `문제 번호 * 100 + 순번` (공개 테스트케이스가 앞)
+/// - `steps`: 순서대로 실행할 동작 (`Step`)
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Scenario {
+    /// 워커 셔플에 쓰는 난수 시드
+    #[serde(default)]
+    seed: u64,
+    #[serde(default)]
+    config: Config,
+    #[serde(default)]
+    problems: Vec<Problem>,
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Problem {
+    no: i32,
+    #[serde(default)]
+    public: i32,
+    #[serde(default)]
+    private: i32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case", deny_unknown_fields)]
+enum Step {
+    /// 워커 접속
+    Join {
+        worker: String,
+        #[serde(default)]
+        precise: bool,
+    },
+    /// 새 제출. 다음 폴링 때 가져감
+    Submit {
+        id: i32,
+        stud_id: i32,
+        problem: i32,
+        /// "실행" 버튼
+        #[serde(default)]
+        quick: bool,
+        /// 대회 시간 중의 제출
+        #[serde(default)]
+        contest: bool,
+    },
+    /// 워커가 지금 가진 작업의 결과를 보냄
+    Result {
+        worker: String,
+        #[serde(default = "default_verdict")]
+        verdict: String,
+        /// ms
+        #[serde(default)]
+        runtime: usize,
+    },
+    /// 워커 연결이 끊김
+    Disconnect { worker: String },
+    /// 워커 watchdog 기한이 지남 (작업을 다시 넣고 연결을 끊음)
+    Expire { worker: String },
+    /// 워커가 속도 측정을 마침 (`calibration.enabled`일 때)
+    Calibrated { worker: String, speed_factor: f64 },
+    /// 관리자 API: 워커 내보내기
+    Drain { worker: String },
+    /// 관리자 API: 제출 재채점
+    Rejudge { submission: i32 },
+    /// 관리자 API: 채점 중단
+    Cancel { submission: i32 },
+    /// 관리자 API: 입수 중지/재개
+    PauseIntake { paused: bool },
+    /// 가져간 제출의 lease가 모두 만료됨 (DB 장애로 갱신이 밀린 상황). 다음 lease 만료 확인에서 다시 가져옴
+    ExpireLeases,
+    /// 가상 시간을 흘려보냄. 그 사이에 울리는 타이머(폴링, lease 갱신, lease 만료 확인)를 순서대로 실행함
+    Advance { ms: u64 },
+    /// 직전 `expect` 이후의 배정, 최종 판정, requeue가 정확히 이것뿐이고 이 순서대로 나왔어야 함
+    Expect {
+        #[serde(default)]
+        dispatched: Vec<Dispatch>,
+        #[serde(default)]
+        finished: Vec<Finish>,
+        /// 사유와 함께 다시 큐에 넣은 테스트케이스 (watchdog)
+        #[serde(default)]
+        requeued: Vec<Requeue>,
+    },
+}
+
+fn default_verdict() -> String {
+    "accepted".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Dispatch {
+    worker: String,
+    submission: i32,
+    testcase: i32,
+}
+impl std::fmt::Display for Dispatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} <- {}/{}",
+            self.worker, self.submission, self.testcase
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Finish {
+    submission: i32,
+    verdict: String,
+    /// 검증을 건너뛰고 채점해서 `needs_review`가 붙었는지
+    #[serde(default)]
+    needs_review: bool,
+}
+impl std::fmt::Display for Finish {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} = {}", self.submission, self.verdict)?;
+        if self.needs_review {
+            write!(f, " (needs review)")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Requeue {
+    submission: i32,
+    testcase: i32,
+    reason: String,
+}
+impl std::fmt::Display for Requeue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{} ({})", self.submission, self.testcase, self.reason)
+    }
+}
+
+fn parse_verdict(name: &str) -> Result<TestCaseJudgeResultInner, String> {
+    VERDICTS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, verdict)| verdict.clone())
+        .ok_or(format!("unknown verdict {:?}", name))
+}
+
+/// `finish_submission`에 넘어오는 판정 문자열 (`Display`)을 시나리오 이름으로
+fn verdict_name(extra: &str) -> String {
+    extra
+        .parse::<TestCaseJudgeResultInner>()
+        .ok()
+        .and_then(|verdict| VERDICTS.iter().find(|(_, v)| *v == verdict))
+        .map(|(name, _)| name.to_string())
+        .unwrap_or(extra.to_string())
+}
+
+fn datetime(s: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+}
+
+/// `judge simulate <시나리오>`. 종료 코드를 반환 (0: 통과, 1: `expect` 실패, 2: 시나리오 오류)
+pub fn main(path: &str) -> i32 {
+    let scenario: Scenario = match std::fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| toml::from_str(&text).map_err(|e| e.to_string()))
+    {
+        Ok(scenario) => scenario,
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            return 2;
+        }
+    };
+
+    let Scenario {
+        seed,
+        mut config,
+        problems,
+        steps,
+    } = scenario;
+    if config.coordinator.id.is_empty() {
+        config.coordinator.id = "simulation".to_string();
+    }
+    // DB에 접속하지 않지만 검증을 통과하도록
+    if config.database.url.is_empty() {
+        config.database.url = "mysql://localhost/simulation".to_string();
+    }
+    if let Err(e) = config.validate() {
+        eprintln!("{}: {}", path, e);
+        return 2;
+    }
+    config::init(config);
+    logging::init(&config::get().logging);
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+
+    let total = steps.len();
+    match rt.block_on(run(seed, &problems, steps)) {
+        Ok(()) => {
+            println!("ok: {} steps", total);
+            0
+        }
+        Err(e) => {
+            println!("FAILED: {}", e);
+            1
+        }
+    }
+}
+
+async fn run(seed: u64, problems: &[Problem], steps: Vec<Step>) -> Result<(), String> {
+    let mut sim = Simulation::new(seed, problems);
+
+    // 타이머의 첫 tick (lease 갱신, lease 만료 확인)은 시작하자마자 울림
+    sim.advance_to(Duration::ZERO).await;
+
+    for (i, step) in steps.into_iter().enumerate() {
+        sim.step(step)
+            .await
+            .map_err(|e| format!("step {}: {}", i + 1, e))?;
+    }
+
+    Ok(())
+}
+
+/// 가짜 워커. 실제 워커(listener)처럼 작업 중이거나 내보내는 중이면 새 작업을 거절함
+struct SimWorker {
+    name: String,
+    channel_id: usize,
+    rx: RX,
+    is_connected: bool,
+    is_calibrating: bool,
+    is_draining: bool,
+    current: Option<(Submission, TestCase)>,
+}
+
+struct Simulation {
+    clock: Arc<VirtualClock>,
+    store: Arc<MemoryStore>,
+    scheduler: Scheduler<MemoryStore>,
+    problems: BTreeSet<i32>,
+    /// 접속한 순서
+    workers: Vec<SimWorker>,
+
+    next_reap: Duration,
+    next_renewal: Duration,
+    next_poll: Duration,
+
+    /// 직전 `expect` 이후
+    dispatched: Vec<Dispatch>,
+    finished: Vec<Finish>,
+    requeued: Vec<Requeue>,
+    /// 제출마다 관리자 API에서 이미 읽은 requeue 사유 수
+    requeues_seen: BTreeMap<i32, usize>,
+}
+impl Simulation {
+    fn new(seed: u64, problems: &[Problem]) -> Self {
+        let clock = Arc::new(VirtualClock::new());
+        let store = Arc::new(MemoryStore::new());
+        for problem in problems {
+            for seq in 1..=problem.public + problem.private {
+                store.add_testcase(TestCase {
+                    id: problem.no * 100 + seq,
+                    input: String::new(),
+                    output: String::new(),
+                    problem_id: problem.no,
+                    is_public: seq <= problem.public,
+                    runtime: None,
+                    memory_limit: None,
+                    is_decimal_mode: 0,
+                });
+            }
+        }
+        store.set_contest_window(ContestWindow {
+            start_at: Some(datetime(CONTEST_START_AT)),
+            end_at: Some(datetime(CONTEST_END_AT)),
+        });
+
+        // 이벤트와 webhook은 받는 쪽이 없어도 됨
+        let (tx_events, _) = tokio::sync::broadcast::channel(16);
+        let (tx_webhooks, _) = webhook::channel();
+        let scheduler = Scheduler::new(
+            clock.clone(),
+            store.clone(),
+            StdRng::seed_from_u64(seed),
+            tx_events,
+            tx_webhooks,
+        );
+
+        Self {
+            clock,
+            store,
+            scheduler,
+            problems: problems.iter().map(|problem| problem.no).collect(),
+            workers: vec![],
+            next_reap: Duration::ZERO,
+            next_renewal: Duration::ZERO,
+            next_poll: config::get().scheduler.poll_interval_min(),
+            dispatched: vec![],
+            finished: vec![],
+            requeued: vec![],
+            requeues_seen: BTreeMap::new(),
+        }
+    }
+
+    fn log(&self, msg: impl std::fmt::Display) {
+        println!("[{:>9.3}s] {}", self.clock.elapsed().as_secs_f64(), msg);
+    }
+
+    fn worker(&self, name: &str) -> Result<usize, String> {
+        self.workers
+            .iter()
+            .position(|worker| worker.is_connected && worker.name == name)
+            .ok_or(format!("worker {:?} is not connected", name))
+    }
+
+    async fn step(&mut self, step: Step) -> Result<(), String> {
+        match step {
+            Step::Join { worker, precise } => {
+                if self.worker(&worker).is_ok() {
+                    return Err(format!("worker {:?} is already connected", worker));
+                }
+                self.workers.retain(|w| w.name != worker);
+                self.log(format_args!(
+                    "join {} ({})",
+                    worker,
+                    if precise { "precise" } else { "quick" }
+                ));
+
+                let (tx, rx) = tokio::sync::mpsc::channel(16);
+                self.workers.push(SimWorker {
+                    name: worker,
+                    channel_id: 0,
+                    rx,
+                    is_connected: true,
+                    is_calibrating: false,
+                    is_draining: false,
+                    current: None,
+                });
+                self.scheduler
+                    .handle(ChannelMessage::NewChannel(tx, precise))
+                    .await;
+            }
+            Step::Submit {
+                id,
+                stud_id,
+                problem,
+                quick,
+                contest,
+            } => {
+                if !self.problems.contains(&problem) {
+                    return Err(format!("problem {} is not defined", problem));
+                }
+                self.log(format_args!(
+                    "submit {} (stud {}, problem {}, {})",
+                    id,
+                    stud_id,
+                    problem,
+                    if quick { "quick" } else { "precise" }
+                ));
+
+                self.store.add_submission(Submission {
+                    id,
+                    stud_id,
+                    run_type: if quick {
+                        SubmissionType::Quick
+                    } else {
+                        SubmissionType::Precise
+                    },
+                    problem_no: problem,
+                    lang: SubmissionLanguage::C,
+                    code: String::new(),
+                    state: SubmissionState::Submitted,
+                    extra: None,
+                    result: None,
+                    submit_at: datetime(if contest {
+                        CONTEST_SUBMIT_AT
+                    } else {
+                        PRACTICE_SUBMIT_AT
+                    }),
+                    runtime: None,
+                    memory: None,
+                    score: None,
+                    generation: 0,
+                });
+            }
+            Step::Result {
+                worker,
+                verdict,
+                runtime,
+            } => {
+                let i = self.worker(&worker)?;
+                let result_inner = parse_verdict(&verdict)?;
+                let Some((submission, testcase)) = self.workers[i].current.take() else {
+                    return Err(format!("worker {:?} has no task", worker));
+                };
+                self.log(format_args!(
+                    "result {} -> {}/{} {}",
+                    worker, submission.id, testcase.id, verdict
+                ));
+
+                let result = TestCaseJudgeResult::new(
+                    submission.id,
+                    testcase.id,
+                    matches!(result_inner, TestCaseJudgeResultInner::Accepted),
+                    Some(String::new()),
+                    Some(runtime),
+                    Some(0),
+                    Some(String::new()),
+                    format!("sim-{}", worker),
+                );
+                let channel_id = self.workers[i].channel_id;
+                self.scheduler
+                    .handle(ChannelMessage::WorkDone(
+                        channel_id,
+                        submission,
+                        testcase,
+                        result,
+                        result_inner,
+                    ))
+                    .await;
+
+                // 내보내는 중이던 워커는 결과를 보낸 뒤 종료함
+                if self.workers[i].is_draining {
+                    self.disconnect(i).await;
+                }
+            }
+            Step::Disconnect { worker } => {
+                let i = self.worker(&worker)?;
+                self.log(format_args!("disconnect {}", worker));
+                self.disconnect(i).await;
+            }
+            Step::Expire { worker } => {
+                let i = self.worker(&worker)?;
+                let Some((submission, testcase)) = self.workers[i].current.take() else {
+                    return Err(format!("worker {:?} has no task", worker));
+                };
+                self.log(format_args!(
+                    "expire {} ({}/{})",
+                    worker, submission.id, testcase.id
+                ));
+
+                let channel_id = self.workers[i].channel_id;
+                self.scheduler
+                    .handle(ChannelMessage::Expired(
+                        channel_id,
+                        submission,
+                        testcase,
+                        format!("watchdog expired on channel {}", channel_id),
+                    ))
+                    .await;
+                self.disconnect(i).await;
+            }
+            Step::Calibrated {
+                worker,
+                speed_factor,
+            } => {
+                let i = self.worker(&worker)?;
+                self.log(format_args!("calibrated {} ({})", worker, speed_factor));
+
+                self.workers[i].is_calibrating = false;
+                let channel_id = self.workers[i].channel_id;
+                self.scheduler
+                    .handle(ChannelMessage::Calibrated(channel_id, speed_factor))
+                    .await;
+            }
+            Step::Drain { worker } => {
+                let i = self.worker(&worker)?;
+                self.log(format_args!("drain {}", worker));
+
+                let channel_id = self.workers[i].channel_id;
+                self.scheduler
+                    .handle(ChannelMessage::Drain(channel_id))
+                    .await;
+            }
+            Step::Rejudge { submission } => {
+                self.log(format_args!("rejudge {}", submission));
+
+                let (tx, mut rx) = oneshot::channel();
+                self.scheduler
+                    .handle(ChannelMessage::Admin(AdminRequest::Rejudge(submission, tx)))
+                    .await;
+                rx.try_recv()
+                    .map_err(|_| "rejudge: no reply".to_string())??;
+            }
+            Step::Cancel { submission } => {
+                self.log(format_args!("cancel {}", submission));
+
+                let (tx, mut rx) = oneshot::channel();
+                self.scheduler
+                    .handle(ChannelMessage::Admin(AdminRequest::Cancel(submission, tx)))
+                    .await;
+                if !rx.try_recv().unwrap_or(false) {
+                    return Err(format!("submission {} is not being judged", submission));
+                }
+            }
+            Step::PauseIntake { paused } => {
+                self.log(format_args!("pause intake = {}", paused));
+
+                let (tx, _rx) = oneshot::channel();
+                self.scheduler
+                    .handle(ChannelMessage::Admin(AdminRequest::PauseIntake(paused, tx)))
+                    .await;
+            }
+            Step::ExpireLeases => {
+                self.log("expire leases");
+                self.store.expire_leases();
+            }
+            Step::Advance { ms } => {
+                let target = self.clock.elapsed() + Duration::from_millis(ms);
+                self.advance_to(target).await;
+            }
+            Step::Expect {
+                dispatched,
+                finished,
+                requeued,
+            } => {
+                self.log("expect");
+                compare(
+                    "dispatched",
+                    dispatched,
+                    std::mem::take(&mut self.dispatched),
+                )?;
+                compare("finished", finished, std::mem::take(&mut self.finished))?;
+                compare("requeued", requeued, std::mem::take(&mut self.requeued))?;
+            }
+        }
+
+        self.settle().await;
+        Ok(())
+    }
+
+    /// `target`까지 시간을 흘려보내면서 그 사이의 타이머를 울림. 같은 시각이면 lease 만료 확인, lease 갱신, 폴링 순
+    async fn advance_to(&mut self, target: Duration) {
+        let coordinator = &config::get().coordinator;
+
+        loop {
+            // 입수가 멈춰 있으면 lease 만료 확인은 건너뜀 (main_async와 같음)
+            let reap = (!self.scheduler.is_intake_paused()).then_some(self.next_reap);
+            let next = [reap, Some(self.next_renewal), Some(self.next_poll)]
+                .into_iter()
+                .flatten()
+                .min()
+                .unwrap();
+            if next > target {
+                break;
+            }
+            self.clock.set(next);
+
+            if reap == Some(next) {
+                self.scheduler.reap().await;
+                self.next_reap += coordinator.reap_interval();
+            }
+            if self.next_renewal == next {
+                self.scheduler.renew_leases().await;
+                self.next_renewal += coordinator.renew_interval();
+            }
+            if self.next_poll == next {
+                let poll_interval = self.scheduler.poll().await;
+                self.next_poll = self.clock.elapsed() + poll_interval;
+            }
+
+            self.settle().await;
+        }
+
+        self.clock.set(target);
+    }
+
+    /// 워커 쪽에 쌓인 메시지를 더 이상 없을 때까지 처리함 (거절하면 다시 배정될 수 있으므로)
+    async fn settle(&mut self) {
+        loop {
+            let mut is_idle = true;
+
+            for i in 0..self.workers.len() {
+                while let Ok(msg) = self.workers[i].rx.try_recv() {
+                    is_idle = false;
+                    if self.workers[i].is_connected {
+                        self.receive(i, msg).await;
+                    }
+                }
+            }
+
+            for submission in self.store.take_finalized() {
+                is_idle = false;
+                let extra = self
+                    .store
+                    .submission(submission)
+                    .and_then(|submission| submission.extra)
+                    .unwrap_or_default();
+                let finish = Finish {
+                    submission,
+                    verdict: verdict_name(&extra),
+                    needs_review: self.store.needs_review(submission),
+                };
+                self.log(format_args!("finished {}", finish));
+                self.finished.push(finish);
+            }
+
+            self.collect_requeues().await;
+            if is_idle {
+                return;
+            }
+        }
+    }
+
+    /// 관리자 API의 제출 목록에서 새로 생긴 requeue 사유를 모음
+    async fn collect_requeues(&mut self) {
+        let (tx, mut rx) = oneshot::channel();
+        self.scheduler
+            .handle(ChannelMessage::Admin(AdminRequest::Submissions(tx)))
+            .await;
+        let Ok(views) = rx.try_recv() else { return };
+
+        // 끝났거나 재채점으로 다시 들어온 제출은 처음부터 셈
+        self.requeues_seen
+            .retain(|id, _| views.iter().any(|view| view.submission_id == *id));
+        let mut requeued = vec![];
+        for view in views {
+            let seen = self.requeues_seen.entry(view.submission_id).or_default();
+            if view.requeues.len() < *seen {
+                *seen = 0;
+            }
+            requeued.extend(view.requeues[*seen..].iter().map(|requeue| Requeue {
+                submission: view.submission_id,
+                testcase: requeue.testcase_id,
+                reason: requeue.reason.clone(),
+            }));
+            *seen = view.requeues.len();
+        }
+
+        for requeue in requeued {
+            self.log(format_args!("requeued {}", requeue));
+            self.requeued.push(requeue);
+        }
+    }
+
+    async fn receive(&mut self, i: usize, msg: ChannelMessage) {
+        let worker = &mut self.workers[i];
+
+        match msg {
+            ChannelMessage::SetChannelId(channel_id) => worker.channel_id = channel_id,
+            ChannelMessage::Calibrate => {
+                worker.is_calibrating = true;
+                self.log(format_args!("calibrate {}", self.workers[i].name));
+            }
+            ChannelMessage::WorkStart(submission, testcase) => {
+                let dispatch = Dispatch {
+                    worker: worker.name.clone(),
+                    submission: submission.id,
+                    testcase: testcase.id,
+                };
+
+                if worker.current.is_some() || worker.is_draining || worker.is_calibrating {
+                    let channel_id = worker.channel_id;
+                    self.log(format_args!("refused {}", dispatch));
+                    self.scheduler
+                        .handle(ChannelMessage::Refuse(channel_id, submission, testcase))
+                        .await;
+                    return;
+                }
+
+                worker.current = Some((submission, testcase));
+                self.log(format_args!("dispatch {}", dispatch));
+                self.dispatched.push(dispatch);
+            }
+            ChannelMessage::Drain(_) => {
+                worker.is_draining = true;
+
+                // 진행중인 작업이 없으면 바로 종료
+                if worker.current.is_none() {
+                    self.disconnect(i).await;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// 실제 워커처럼 들고 있던 작업을 같이 알려줌
+    async fn disconnect(&mut self, i: usize) {
+        let worker = &mut self.workers[i];
+        worker.is_connected = false;
+        let (submission, testcase) = worker.current.take().unzip();
+
+        let channel_id = worker.channel_id;
+        self.log(format_args!("disconnected {}", self.workers[i].name));
+        self.scheduler
+            .handle(ChannelMessage::Shutdown(channel_id, submission, testcase))
+            .await;
+    }
+}
+
+fn compare<T: PartialEq + std::fmt::Display>(
+    what: &str,
+    expected: Vec<T>,
+    actual: Vec<T>,
+) -> Result<(), String> {
+    if expected == actual {
+        return Ok(());
+    }
+
+    let list = |items: &[T]| {
+        items
+            .iter()
+            .map(|item| item.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    Err(format!(
+        "{}\n  expected: [{}]\n  actual:   [{}]",
+        what,
+        list(&expected),
+        list(&actual)
+    ))
+}
@@ -0,0 +1,796 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use rusqlite::types::{FromSql, Value};
+use rusqlite::{
+    named_params, params, params_from_iter, Connection, OptionalExtension, Transaction,
+    TransactionBehavior,
+};
+
+use crate::{
+    config::{self, CoordinatorConfig},
+    db::{self, DbError},
+    migrate::{self, Migration},
+    store::{Finished, JudgeStore, Verdict},
+    task_manager::TestCaseRun,
+    types::*,
+};
+
+/// 파일 하나에 모든 테이블을 두는 `JudgeStore`. 실습실 같은 작은 환경용
+///
+/// 연결 하나를 잠가서 쓰고, 쿼리는 blocking 스레드에서 실행함
+pub struct SqliteStore {
+    conn: Arc<Mutex<Connection>>,
+}
+impl SqliteStore {
+    /// 파일이 없으면 만듦. 테이블은 `judge migrate`로 만듦
+    pub fn open(path: &str) -> Result<Self, DbError> {
+        let conn = Connection::open(path)?;
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+        conn.pragma_update(None, "foreign_keys", true)?;
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// blocking 스레드에서 `f`를 실행함. 일시적인 오류(다른 프로세스가 잠금)는 다시 시도함
+    async fn run<T, F>(&self, statement: &str, f: F) -> Result<T, DbError>
+    where
+        T: Send + 'static,
+        F: Fn(&mut Connection) -> Result<T, DbError> + Send + Sync + 'static,
+    {
+        let f = Arc::new(f);
+
+        db::retry(statement, || {
+            let conn = self.conn.clone();
+            let f = f.clone();
+            async move {
+                tokio::task::spawn_blocking(move || f(&mut conn.lock().unwrap()))
+                    .await
+                    .expect("sqlite task panicked")
+            }
+        })
+        .await
+    }
+}
+
+impl JudgeStore for SqliteStore {
+    async fn claim_submissions(
+        &self,
+        precise_avail: usize,
+        quick_avail: usize,
+    ) -> Result<(Vec<Submission>, Vec<Submission>), DbError> {
+        let mut claimed = self
+            .run("claim", move |conn| {
+                claim_where(
+                    conn,
+                    &[
+                        (
+                            "queued = 0 AND type = 1 ORDER BY id LIMIT ?",
+                            vec![Value::from(precise_avail as i64)],
+                        ),
+                        (
+                            "queued = 0 AND type = 0 ORDER BY id LIMIT ?",
+                            vec![Value::from(quick_avail as i64)],
+                        ),
+                    ],
+                )
+            })
+            .await?;
+
+        let quick = claimed.pop().unwrap_or_default();
+        let precise = claimed.pop().unwrap_or_default();
+        Ok((precise, quick))
+    }
+    async fn reclaim_expired_submissions(&self, limit: usize) -> Result<Vec<Submission>, DbError> {
+        Ok(self
+            .run("claim", move |conn| {
+                claim_where(
+                    conn,
+                    &[(
+                        "queued = 1 AND state <> 2 AND (claim_expires_at IS NULL OR claim_expires_at < datetime('now')) ORDER BY id LIMIT ?",
+                        vec![Value::from(limit as i64)],
+                    )],
+                )
+            })
+            .await?
+            .pop()
+            .unwrap_or_default())
+    }
+    async fn claim_submission(&self, id: i32) -> Result<Option<Submission>, DbError> {
+        let coordinator = config::get().coordinator.clone();
+
+        self.run("claim", move |conn| {
+            claim_submission(conn, &coordinator, id)
+        })
+        .await
+    }
+    async fn cancel_submission(&self, id: i32) -> Result<(), DbError> {
+        let coordinator_id = config::get().coordinator.id.clone();
+
+        self.run("UPDATE", move |conn| {
+            conn.execute(
+                "UPDATE Submit SET state = 2, extra = 'cancelled', claim_expires_at = NULL WHERE id = :id AND claimed_by = :claimed_by",
+                named_params! { ":id": id, ":claimed_by": coordinator_id },
+            )?;
+            Ok(())
+        })
+        .await
+    }
+    async fn renew_claims(&self, ids: Vec<i32>) -> Result<(), DbError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let coordinator = &config::get().coordinator;
+        let sql = format!(
+            "UPDATE Submit SET claim_expires_at = datetime('now', ?) WHERE claimed_by = ? AND id IN ({})",
+            placeholders(ids.len())
+        );
+        let mut params = vec![
+            Value::from(lease(coordinator.lease_secs)),
+            Value::from(coordinator.id.clone()),
+        ];
+        params.extend(ids.into_iter().map(Value::from));
+
+        self.run("UPDATE", move |conn| {
+            conn.execute(&sql, params_from_iter(params.iter()))?;
+            Ok(())
+        })
+        .await
+    }
+    async fn list_testcase(&self, problem_no: i32) -> Result<Vec<TestCase>, DbError> {
+        self.run("SELECT", move |conn| {
+            let mut stmt = conn.prepare_cached("SELECT * FROM Testcase WHERE problem_id = ?")?;
+            let mut rows = stmt.query(params![problem_no])?;
+
+            let mut testcases = Vec::new();
+            while let Some(row) = rows.next()? {
+                testcases.push(testcase_from_row(row)?);
+            }
+            Ok(testcases)
+        })
+        .await
+    }
+    async fn contest_window(&self) -> Result<ContestWindow, DbError> {
+        self.run("SELECT", |conn| {
+            let mut stmt =
+                conn.prepare_cached("SELECT key, val FROM config WHERE key IN (?, ?)")?;
+            let mut rows = stmt.query(params!["START_AT", "END_AT"])?;
+
+            let mut window = ContestWindow::default();
+            while let Some(row) = rows.next()? {
+                let key: String = column(row, "config", "key")?;
+                let at = match column::<Option<String>>(row, "config", "val")? {
+                    Some(val) => Some(parse_datetime("config", "val", &val)?),
+                    None => None,
+                };
+
+                match key.as_str() {
+                    "START_AT" => window.start_at = at,
+                    "END_AT" => window.end_at = at,
+                    _ => (),
+                }
+            }
+            Ok(window)
+        })
+        .await
+    }
+    async fn upsert_testcase_judge(
+        &self,
+        submission_id: i32,
+        generation: u32,
+        testcase_id: i32,
+        attempt: u32,
+        result: &TestCaseJudgeResult,
+        result_inner: &TestCaseJudgeResultInner,
+    ) -> Result<(), DbError> {
+        let run = (attempt, result.clone(), result_inner.clone());
+
+        self.run("INSERT", move |conn| {
+            upsert_testcase_judge(conn, submission_id, generation, testcase_id, &run)?;
+            Ok(())
+        })
+        .await
+    }
+    async fn list_testcase_judge(
+        &self,
+        submission_id: i32,
+        generation: u32,
+    ) -> Result<Vec<TestCaseRun>, DbError> {
+        const TABLE: &str = "Testcase_judge";
+
+        self.run("SELECT", move |conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT * FROM Testcase_judge WHERE submit_id = ? AND generation = ? ORDER BY id",
+            )?;
+            let mut rows = stmt.query(params![submission_id, generation])?;
+
+            let mut latest = BTreeMap::new();
+            while let Some(row) = rows.next()? {
+                let attempt: u32 = column(row, TABLE, "attempt")?;
+                let result_extra: Option<String> = column(row, TABLE, "result_extra")?;
+                let result_inner = result_extra
+                    .as_deref()
+                    .and_then(|v| v.parse().ok())
+                    .ok_or_else(|| RowError {
+                        table: TABLE,
+                        column: "result_extra",
+                        reason: format!("unknown verdict {:?}", result_extra),
+                    })?;
+
+                let result = testcase_judge_from_row(row)?;
+                latest.insert(
+                    (result.testcase_id, attempt),
+                    (attempt, result, result_inner),
+                );
+            }
+            Ok(latest.into_values().collect())
+        })
+        .await
+    }
+    async fn finish_submission(
+        &self,
+        submission: &Submission,
+        verdict: &Verdict,
+        runs: &[TestCaseRun],
+    ) -> Result<Finished, DbError> {
+        let submission = submission.clone();
+        let verdict = verdict.clone();
+        let runs = runs.to_vec();
+        let coordinator_id = config::get().coordinator.id.clone();
+
+        self.run("finish", move |conn| {
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+            // lease가 만료돼서 다른 코디네이터가 가져갔거나 그 사이에 재채점된 제출이면 덮어쓰지 않음
+            let claimed_by: Option<String> = tx
+                .query_row(
+                    "SELECT claimed_by FROM Submit WHERE id = ? AND generation = ?",
+                    params![submission.id, submission.generation],
+                    |row| row.get(0),
+                )
+                .optional()?
+                .flatten();
+            if claimed_by.as_deref() != Some(coordinator_id.as_str()) {
+                return Ok(Finished::NotOwner);
+            }
+
+            // MySQL 쪽과 같은 점수 계산. 시간 차이는 unix time으로 뺌
+            let (retries, secs): (i64, Option<i64>) = tx.query_row(
+                "
+    SELECT
+        count(*) AS tries,
+        (SELECT strftime('%s', :submit_at) - strftime('%s', val) FROM config WHERE key = 'START_AT') AS sec_diff
+    FROM Submit WHERE stud_id = :stud_id AND type = 1 AND problemNo = :problem_no AND result = 0
+    AND (id < (SELECT min(id) FROM Submit WHERE stud_id = :stud_id AND type = 1 AND problemNo = :problem_no AND result = 1))
+    ",
+                named_params! {
+                    ":submit_at": submission.submit_at.format("%Y-%m-%d %T").to_string(),
+                    ":stud_id": submission.stud_id,
+                    ":problem_no": submission.problem_no,
+                },
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+            let score = if verdict.is_correct {
+                retries.max(0) * 20 + secs.unwrap_or(0).max(0) / 60
+            } else {
+                0
+            };
+
+            for run in &runs {
+                upsert_testcase_judge(
+                    &tx,
+                    submission.id,
+                    submission.generation,
+                    run.1.testcase_id,
+                    run,
+                )?;
+            }
+            tx.execute(
+                "UPDATE Submit SET score = :score, result = :result, extra = :extra, memory = :memory, runtime = :runtime, state = 2, needs_review = MAX(needs_review, :needs_review), claim_expires_at = NULL WHERE id = :id AND claimed_by = :claimed_by",
+                named_params! {
+                    ":score": score,
+                    ":result": if verdict.is_correct { 0 } else { 1 },
+                    ":extra": verdict.extra,
+                    ":memory": verdict.memory as i64,
+                    ":runtime": verdict.runtime as i64,
+                    ":needs_review": verdict.needs_review,
+                    ":id": submission.id,
+                    ":claimed_by": coordinator_id,
+                },
+            )?;
+
+            tx.commit()?;
+            Ok(Finished::Saved)
+        })
+        .await
+    }
+    async fn schema_version(&self) -> Result<u32, DbError> {
+        self.run("SELECT", |conn| schema_version(conn)).await
+    }
+    async fn migrate(&self) -> Result<Vec<&'static Migration>, DbError> {
+        self.run("migrate", apply_migrations).await
+    }
+}
+
+#[allow(dead_code)]
+impl SqliteStore {
+    async fn update_user_problem_stat(
+        &self,
+        stud_id: i32,
+        problem_no: i32,
+        score: usize,
+    ) -> Result<(), DbError> {
+        self.run("UPDATE", move |conn| {
+            conn.execute(
+                "UPDATE user_problem_stat SET score = ? WHERE stud_id = ? AND problem_no = ?",
+                params![score as i64, stud_id, problem_no],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+}
+
+/// 마이그레이션 도입 전부터 있던 테이블에 빠진 컬럼을 추가함 (`migrate::LEGACY_COLUMNS`)
+fn upgrade_legacy(tx: &Transaction) -> Result<(), DbError> {
+    for column in migrate::LEGACY_COLUMNS {
+        let existing = tx
+            .prepare("SELECT name FROM pragma_table_info(?)")?
+            .query_map(params![column.table], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        if !column.is_missing(&existing) {
+            continue;
+        }
+
+        // 이름은 바인딩할 수 없음. 코드에 있는 상수만 씀
+        tx.execute_batch(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            column.table, column.name, column.sqlite
+        ))?;
+        tracing::info!(
+            table = column.table,
+            column = column.name,
+            "added missing column"
+        );
+    }
+    Ok(())
+}
+
+/// `db::claim_where`와 같음. SQLite에는 `SKIP LOCKED`가 없으므로 쓰기 트랜잭션으로 먼저 잠금
+fn claim_where(
+    conn: &mut Connection,
+    conditions: &[(&str, Vec<Value>)],
+) -> Result<Vec<Vec<Submission>>, DbError> {
+    let coordinator = &config::get().coordinator;
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+    let mut claimed = Vec::with_capacity(conditions.len());
+    for (condition, params) in conditions {
+        let mut stmt = tx.prepare(&format!("SELECT * FROM Submit WHERE {}", condition))?;
+        let mut rows = stmt.query(params_from_iter(params.iter()))?;
+
+        let mut submissions = Vec::new();
+        while let Some(row) = rows.next()? {
+            match submission_from_row(row) {
+                Ok(submission) => submissions.push(submission),
+                Err(e) => {
+                    let id = row.get::<_, i32>("id").ok();
+                    tracing::error!(submission_id = ?id, error = %e, "cannot read submission, skipping")
+                }
+            }
+        }
+        claimed.push(submissions);
+    }
+
+    let ids = claimed
+        .iter()
+        .flatten()
+        .map(|submission: &Submission| Value::from(submission.id))
+        .collect::<Vec<_>>();
+    if !ids.is_empty() {
+        let sql = format!(
+            "UPDATE Submit SET queued = 1, claimed_by = ?, claim_expires_at = datetime('now', ?) WHERE id IN ({})",
+            placeholders(ids.len())
+        );
+        let mut params = vec![
+            Value::from(coordinator.id.clone()),
+            Value::from(lease(coordinator.lease_secs)),
+        ];
+        params.extend(ids);
+        tx.execute(&sql, params_from_iter(params.iter()))?;
+    }
+
+    tx.commit()?;
+    Ok(claimed)
+}
+
+/// 관리자 재채점 (`JudgeStore::claim_submission`). 새 채점이므로 가져가면서 generation을 올리고 올린 값을 돌려줌
+fn claim_submission(
+    conn: &mut Connection,
+    coordinator: &CoordinatorConfig,
+    id: i32,
+) -> Result<Option<Submission>, DbError> {
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+    let submission = tx
+        .prepare(
+            "UPDATE Submit SET queued = 1, claimed_by = ?1, claim_expires_at = datetime('now', ?2), state = ?3, generation = generation + 1 WHERE id = ?4 AND (state = 2 OR claimed_by IS NULL OR claimed_by = ?1 OR claim_expires_at IS NULL OR claim_expires_at < datetime('now')) RETURNING *",
+        )?
+        .query(params![
+            coordinator.id,
+            lease(coordinator.lease_secs),
+            SubmissionState::InProgress as i32,
+            id
+        ])?
+        .next()?
+        .map(submission_from_row)
+        .transpose()?;
+
+    tx.commit()?;
+    Ok(submission)
+}
+
+/// (submit_id, testcase_id, attempt)가 같으면 덮어씀
+fn upsert_testcase_judge(
+    conn: &Connection,
+    submission_id: i32,
+    generation: u32,
+    testcase_id: i32,
+    (attempt, result, result_inner): &TestCaseRun,
+) -> Result<(), DbError> {
+    conn.prepare_cached(
+        "INSERT INTO Testcase_judge (submit_id, generation, testcase_id, output, runtime, result, compile_log, memory, judge_server_id, result_extra, attempt, judge_at) VALUES (:submit_id, :generation, :testcase_id, :output, :runtime, :result, :compile_log, :memory, :judge_server_id, :result_extra, :attempt, CURRENT_TIMESTAMP)
+        ON CONFLICT (submit_id, testcase_id, attempt) DO UPDATE SET generation = excluded.generation, output = excluded.output, runtime = excluded.runtime, result = excluded.result, compile_log = excluded.compile_log, memory = excluded.memory, judge_at = CURRENT_TIMESTAMP, judge_server_id = excluded.judge_server_id, result_extra = excluded.result_extra",
+    )?
+    .execute(named_params! {
+        ":submit_id": submission_id,
+        ":generation": generation,
+        ":testcase_id": testcase_id,
+        ":output": result.output,
+        ":runtime": result.runtime.map(|v| v as i64),
+        ":result": if result.result { 0 } else { 1 },
+        ":compile_log": result.compile_log.clone().unwrap_or_default(),
+        ":memory": result.memory.unwrap_or(0) as i64,
+        ":judge_server_id": result.judge_server_id,
+        ":result_extra": result_inner.to_string(),
+        ":attempt": attempt,
+    })?;
+    Ok(())
+}
+
+fn schema_version(conn: &Connection) -> Result<u32, DbError> {
+    let exists: bool = conn.query_row(
+        "SELECT count(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'schema_version'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        return Ok(0);
+    }
+
+    Ok(conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_version",
+        [],
+        |row| row.get(0),
+    )?)
+}
+
+/// 빠진 컬럼을 추가하고 적용하지 않은 마이그레이션을 적용함 (`JudgeStore::migrate`)
+fn apply_migrations(conn: &mut Connection) -> Result<Vec<&'static Migration>, DbError> {
+    conn.execute_batch(migrate::VERSION_TABLE)?;
+
+    // 한 트랜잭션으로 묶으므로 중간에 실패하면 아무것도 적용되지 않음
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    upgrade_legacy(&tx)?;
+    let pending = migrate::pending(migrate::SQLITE, schema_version(&tx)?);
+    for migration in &pending {
+        tx.execute_batch(migration.sql)?;
+        tx.execute(
+            "INSERT INTO schema_version (version, name) VALUES (?, ?)",
+            params![migration.version, migration.name],
+        )?;
+    }
+    tx.commit()?;
+
+    Ok(pending)
+}
+
+/// `datetime('now', ?)`에 넘길 값
+fn lease(lease_secs: u64) -> String {
+    format!("+{} seconds", lease_secs)
+}
+
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
+
+fn column<T: FromSql>(
+    row: &rusqlite::Row,
+    table: &'static str,
+    name: &'static str,
+) -> Result<T, RowError> {
+    row.get(name).map_err(|e| RowError {
+        table,
+        column: name,
+        reason: e.to_string(),
+    })
+}
+
+/// 정수 코드로 저장한 컬럼. `codes[코드]`
+fn coded<T: Copy>(
+    row: &rusqlite::Row,
+    table: &'static str,
+    name: &'static str,
+    codes: &[T],
+) -> Result<T, RowError> {
+    let code: i64 = column(row, table, name)?;
+
+    usize::try_from(code)
+        .ok()
+        .and_then(|i| codes.get(i))
+        .copied()
+        .ok_or_else(|| RowError {
+            table,
+            column: name,
+            reason: format!("unexpected value {}", code),
+        })
+}
+
+fn parse_datetime(
+    table: &'static str,
+    name: &'static str,
+    value: &str,
+) -> Result<NaiveDateTime, RowError> {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").map_err(|_| RowError {
+        table,
+        column: name,
+        reason: format!("invalid datetime {:?}", value),
+    })
+}
+
+fn submission_from_row(row: &rusqlite::Row) -> Result<Submission, RowError> {
+    const TABLE: &str = "Submit";
+
+    let lang: String = column(row, TABLE, "lang")?;
+    let result = match column::<Option<i64>>(row, TABLE, "result")? {
+        Some(_) => Some(coded(
+            row,
+            TABLE,
+            "result",
+            &[SubmissionResult::Correct, SubmissionResult::Wrong],
+        )?),
+        None => None,
+    };
+
+    Ok(Submission {
+        id: column(row, TABLE, "id")?,
+        stud_id: column(row, TABLE, "stud_id")?,
+        run_type: coded(
+            row,
+            TABLE,
+            "type",
+            &[SubmissionType::Quick, SubmissionType::Precise],
+        )?,
+        problem_no: column(row, TABLE, "problemNo")?,
+        lang: SubmissionLanguage::try_from(lang.clone()).map_err(|_| RowError {
+            table: TABLE,
+            column: "lang",
+            reason: format!("unexpected value {:?}", lang),
+        })?,
+        code: column(row, TABLE, "code")?,
+        state: coded(
+            row,
+            TABLE,
+            "state",
+            &[
+                SubmissionState::Submitted,
+                SubmissionState::InProgress,
+                SubmissionState::Done,
+            ],
+        )?,
+        extra: column(row, TABLE, "extra")?,
+        result,
+        submit_at: parse_datetime(
+            TABLE,
+            "submit_at",
+            &column::<String>(row, TABLE, "submit_at")?,
+        )?,
+        runtime: column(row, TABLE, "runtime")?,
+        memory: column(row, TABLE, "memory")?,
+        score: column(row, TABLE, "score")?,
+        generation: column(row, TABLE, "generation")?,
+    })
+}
+
+fn testcase_from_row(row: &rusqlite::Row) -> Result<TestCase, RowError> {
+    const TABLE: &str = "Testcase";
+
+    Ok(TestCase {
+        id: column(row, TABLE, "id")?,
+        input: column(row, TABLE, "input")?,
+        output: column(row, TABLE, "output")?,
+        problem_id: column(row, TABLE, "problem_id")?,
+        is_public: column(row, TABLE, "isPublic")?,
+        runtime: column(row, TABLE, "runtime")?,
+        memory_limit: column(row, TABLE, "memory_limit")?,
+        is_decimal_mode: column(row, TABLE, "is_decimal_mode")?,
+    })
+}
+
+fn testcase_judge_from_row(row: &rusqlite::Row) -> Result<TestCaseJudgeResult, RowError> {
+    const TABLE: &str = "Testcase_judge";
+
+    Ok(TestCaseJudgeResult {
+        id: column(row, TABLE, "id")?,
+        submit_id: column(row, TABLE, "submit_id")?,
+        testcase_id: column(row, TABLE, "testcase_id")?,
+        output: column(row, TABLE, "output")?,
+        runtime: column(row, TABLE, "runtime")?,
+        memory: column(row, TABLE, "memory")?,
+        result: column::<i32>(row, TABLE, "result")? == 0,
+        compile_log: column(row, TABLE, "compile_log")?,
+        judge_server_id: column(row, TABLE, "judge_server_id")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 마이그레이션 도입 전 스키마 (예전 `types.rs`에 있던 MySQL 정의를 SQLite로 옮긴 것)
+    const LEGACY_SCHEMA: &str = "
+CREATE TABLE Submit (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    stud_id INTEGER NOT NULL,
+    type TEXT,
+    problemNo INTEGER NOT NULL,
+    lang TEXT,
+    code TEXT,
+    state TEXT,
+    extra TEXT,
+    result TEXT,
+    code_size INTEGER,
+    submit_at TEXT,
+    runtime INTEGER,
+    memory INTEGER
+);
+CREATE TABLE Testcase (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    input TEXT,
+    output TEXT,
+    problem_id INTEGER NOT NULL,
+    isPublic INTEGER NOT NULL DEFAULT 1,
+    runtime INTEGER,
+    memory_limit INTEGER
+);
+CREATE TABLE Testcase_judge (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    submit_id INTEGER NOT NULL,
+    output TEXT,
+    runtime INTEGER,
+    result INTEGER,
+    compile_log TEXT
+);
+INSERT INTO Submit (id, stud_id, type, problemNo, lang, code, state, submit_at)
+    VALUES (1, 10, '1', 1, 'c', '', '2', '2024-03-01 10:00:00');
+INSERT INTO Testcase (id, input, output, problem_id) VALUES (1, '', '', 1);
+INSERT INTO Testcase_judge (submit_id, output, runtime, result) VALUES (1, '', 3, 0);
+";
+
+    fn columns(conn: &Connection, table: &str) -> Vec<String> {
+        conn.prepare("SELECT name FROM pragma_table_info(?)")
+            .unwrap()
+            .query_map(params![table], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn migrate_legacy_schema() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(LEGACY_SCHEMA).unwrap();
+
+        let applied = apply_migrations(&mut conn).unwrap();
+        assert_eq!(applied.len(), migrate::VERSION as usize);
+        assert_eq!(schema_version(&conn).unwrap(), migrate::VERSION);
+        for column in migrate::LEGACY_COLUMNS {
+            assert!(
+                !column.is_missing(&columns(&conn, column.table)),
+                "{}.{} is missing",
+                column.table,
+                column.name
+            );
+        }
+
+        // 기존 행은 기본값으로 채워짐
+        let (queued, needs_review, attempt): (i64, i64, i64) = conn
+            .query_row(
+                "SELECT queued, needs_review, attempt FROM Submit JOIN Testcase_judge ON submit_id = Submit.id",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!((queued, needs_review, attempt), (0, 0, 0));
+
+        // 다시 돌려도 아무것도 하지 않음
+        assert!(apply_migrations(&mut conn).unwrap().is_empty());
+    }
+
+    /// `0001_initial`이 기존 테이블을 건너뛰어서 컬럼이 빠진 채로 버전 1이 기록된 DB (MySQL에서 생길 수 있음)
+    #[test]
+    fn migrate_after_partial_upgrade() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(LEGACY_SCHEMA).unwrap();
+        conn.execute_batch(migrate::VERSION_TABLE).unwrap();
+        conn.execute(
+            "INSERT INTO schema_version (version, name) VALUES (1, 'initial')",
+            [],
+        )
+        .unwrap();
+
+        let applied = apply_migrations(&mut conn).unwrap();
+        assert_eq!(
+            applied.iter().map(|m| m.version).collect::<Vec<_>>(),
+            (2..=migrate::VERSION).collect::<Vec<_>>()
+        );
+        assert_eq!(schema_version(&conn).unwrap(), migrate::VERSION);
+    }
+
+    #[test]
+    fn migrate_empty_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+
+        let applied = apply_migrations(&mut conn).unwrap();
+        assert_eq!(applied.len(), migrate::VERSION as usize);
+        for column in migrate::LEGACY_COLUMNS {
+            assert!(!column.is_missing(&columns(&conn, column.table)));
+        }
+    }
+
+    fn coordinator(id: &str) -> CoordinatorConfig {
+        CoordinatorConfig {
+            id: id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// 재채점으로 가져가면 같은 트랜잭션에서 올린 generation을 돌려주고, 다른 코디네이터는 가져가지 못함
+    #[test]
+    fn claim_submission_bumps_generation() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        apply_migrations(&mut conn).unwrap();
+        conn.execute(
+            "INSERT INTO Submit (id, stud_id, type, problemNo, lang, code, state, submit_at) VALUES (1, 10, 1, 1, 'c', '', 2, '2024-03-01 10:00:00')",
+            [],
+        )
+        .unwrap();
+
+        let claimed = claim_submission(&mut conn, &coordinator("a"), 1)
+            .unwrap()
+            .unwrap();
+        assert_eq!(claimed.state, SubmissionState::InProgress);
+        assert_eq!(claimed.generation, 1);
+
+        assert!(claim_submission(&mut conn, &coordinator("b"), 1)
+            .unwrap()
+            .is_none());
+
+        let claimed = claim_submission(&mut conn, &coordinator("a"), 1)
+            .unwrap()
+            .unwrap();
+        assert_eq!(claimed.generation, 2);
+        let stored: u32 = conn
+            .query_row("SELECT generation FROM Submit WHERE id = 1", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(stored, 2);
+    }
+}
@@ -0,0 +1,338 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::{
+    db::DbError,
+    migrate::{self, Migration},
+    task_manager::TestCaseRun,
+    types::*,
+};
+
+/// 채점에 필요한 저장소. 제출과 테스트케이스를 가져오고, 가져간 제출을 표시하고, 결과를 남김
+///
+/// 실제로는 `db::MysqlStore`, `sqlite::SqliteStore`, `postgres::PostgresStore`, DB 없이 돌릴 때는 `MemoryStore`를 씀.
+/// 실패하면 오류를 그대로 돌려주고, 어떻게 할지는 부르는 쪽에서 정함
+pub trait JudgeStore {
+    /// 새 제출을 (정밀 채점, 빠른 채점)으로 나눠서 가져오고 가져간 것으로 표시함
+    async fn claim_submissions(
+        &self,
+        precise_avail: usize,
+        quick_avail: usize,
+    ) -> Result<(Vec<Submission>, Vec<Submission>), DbError>;
+    /// lease가 만료된 제출을 다시 가져옴
+    async fn reclaim_expired_submissions(&self, limit: usize) -> Result<Vec<Submission>, DbError>;
+    /// 관리자 재채점. `generation`을 올려서 가져옴
+    async fn claim_submission(&self, id: i32) -> Result<Option<Submission>, DbError>;
+    async fn cancel_submission(&self, id: i32) -> Result<(), DbError>;
+    async fn renew_claims(&self, ids: Vec<i32>) -> Result<(), DbError>;
+    async fn list_testcase(&self, problem_no: i32) -> Result<Vec<TestCase>, DbError>;
+    async fn contest_window(&self) -> Result<ContestWindow, DbError>;
+    /// 테스트케이스 결과를 바로 남김 (코디네이터가 죽었을 때 이어서 채점하기 위함).
+    /// 같은 (submission_id, testcase_id, attempt)가 이미 있으면 `generation`과 함께 덮어씀
+    async fn upsert_testcase_judge(
+        &self,
+        submission_id: i32,
+        generation: u32,
+        testcase_id: i32,
+        attempt: u32,
+        result: &TestCaseJudgeResult,
+        result_inner: &TestCaseJudgeResultInner,
+    ) -> Result<(), DbError>;
+    /// 이전에 채점하다 만 제출의 테스트케이스 결과. (testcase_id, attempt)마다 가장 마지막 결과만.
+    /// 다른 `generation`(재채점 전)의 결과는 빼고
+    async fn list_testcase_judge(
+        &self,
+        submission_id: i32,
+        generation: u32,
+    ) -> Result<Vec<TestCaseRun>, DbError>;
+    /// 최종 판정을 테스트케이스 결과 전체와 함께 한 트랜잭션으로 남김
+    ///
+    /// 테스트케이스 결과는 `upsert_testcase_judge`처럼 덮어쓰므로 다시 불러도 됨.
+    /// lease가 만료돼서 다른 코디네이터가 가져갔거나 그 사이에 재채점된 (`generation`이 다른) 제출이면
+    /// 아무것도 남기지 않고 `Finished::NotOwner`
+    async fn finish_submission(
+        &self,
+        submission: &Submission,
+        verdict: &Verdict,
+        runs: &[TestCaseRun],
+    ) -> Result<Finished, DbError>;
+    /// 적용한 마지막 마이그레이션 버전. 아무것도 적용하지 않았으면 0
+    async fn schema_version(&self) -> Result<u32, DbError>;
+    /// `judge migrate`. 적용하지 않은 마이그레이션을 적용하고 적용한 것을 돌려줌
+    async fn migrate(&self) -> Result<Vec<&'static Migration>, DbError>;
+
+    /// 새 제출이 들어올 때까지 기다림. 알려주지 못하는 저장소는 끝나지 않으므로 폴링에 맡김
+    async fn wait_for_submission(&self) {
+        std::future::pending::<()>().await
+    }
+}
+
+/// 제출의 최종 판정
+#[derive(Debug, Clone)]
+pub struct Verdict {
+    pub is_correct: bool,
+    /// 판정 문자열 (`TestCaseJudgeResultInner`의 `Display`)
+    pub extra: String,
+    pub runtime: usize,
+    pub memory: usize,
+    /// 교차 검증 결과가 갈림. 관리자가 Testcase_judge의 attempt별 결과를 보고 확인해야 함
+    pub needs_review: bool,
+}
+
+/// `finish_submission`의 결과
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Finished {
+    Saved,
+    /// 다른 코디네이터가 가져갔거나 재채점된 제출이라 남기지 않음. 판정은 지금 채점 중인 쪽이 냄
+    NotOwner,
+}
+
+/// 메모리에만 두는 저장소. DB 없이 시뮬레이션이나 테스트에서 전체 흐름을 돌릴 때 씀
+///
+/// 코디네이터 하나가 쓰는 것을 가정하므로 lease는 `expire_leases`를 부를 때만 만료됨. 점수는 계산하지 않음.
+/// 스키마가 없으므로 항상 최신 버전으로 취급함
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    state: Mutex<MemoryState>,
+}
+#[derive(Debug, Default)]
+struct MemoryState {
+    submissions: BTreeMap<i32, StoredSubmission>,
+    testcases: BTreeMap<i32, TestCase>,
+    /// (submit_id, testcase_id, attempt)별 (generation, 결과)
+    testcase_judge: BTreeMap<(i32, i32, u32), (u32, TestCaseRun)>,
+    contest: ContestWindow,
+    /// 아직 꺼내가지 않은 최종 판정의 제출 번호
+    finalized: Vec<i32>,
+}
+#[derive(Debug)]
+struct StoredSubmission {
+    submission: Submission,
+    queued: bool,
+    needs_review: bool,
+    /// 다음 `reclaim_expired_submissions`에서 다시 가져감
+    lease_expired: bool,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_testcase(&self, testcase: TestCase) {
+        self.state
+            .lock()
+            .unwrap()
+            .testcases
+            .insert(testcase.id, testcase);
+    }
+
+    /// 새 제출. 다음 `claim_submissions`에서 가져감
+    pub fn add_submission(&self, submission: Submission) {
+        self.state.lock().unwrap().submissions.insert(
+            submission.id,
+            StoredSubmission {
+                submission,
+                queued: false,
+                needs_review: false,
+                lease_expired: false,
+            },
+        );
+    }
+
+    pub fn set_contest_window(&self, contest: ContestWindow) {
+        self.state.lock().unwrap().contest = contest;
+    }
+
+    /// 지금 저장된 제출 (최종 판정이 나면 `state`, `result`, `extra` 등이 바뀌어 있음)
+    pub fn submission(&self, id: i32) -> Option<Submission> {
+        self.state
+            .lock()
+            .unwrap()
+            .submissions
+            .get(&id)
+            .map(|stored| stored.submission.clone())
+    }
+
+    pub fn needs_review(&self, id: i32) -> bool {
+        self.state
+            .lock()
+            .unwrap()
+            .submissions
+            .get(&id)
+            .is_some_and(|stored| stored.needs_review)
+    }
+
+    /// 가져가서 아직 판정이 나지 않은 제출의 lease를 모두 만료시킴 (갱신이 밀린 상황)
+    pub fn expire_leases(&self) {
+        for stored in self.state.lock().unwrap().submissions.values_mut() {
+            stored.lease_expired =
+                stored.queued && stored.submission.state != SubmissionState::Done;
+        }
+    }
+
+    /// 직전에 꺼낸 뒤로 최종 판정이 난 제출을 나온 순서대로 꺼냄
+    pub fn take_finalized(&self) -> Vec<i32> {
+        std::mem::take(&mut self.state.lock().unwrap().finalized)
+    }
+}
+
+impl JudgeStore for MemoryStore {
+    async fn claim_submissions(
+        &self,
+        precise_avail: usize,
+        quick_avail: usize,
+    ) -> Result<(Vec<Submission>, Vec<Submission>), DbError> {
+        let mut state = self.state.lock().unwrap();
+
+        let mut precise = Vec::new();
+        let mut quick = Vec::new();
+        for stored in state
+            .submissions
+            .values_mut()
+            .filter(|stored| !stored.queued)
+        {
+            let claimed = match stored.submission.is_precise() {
+                true if precise.len() < precise_avail => &mut precise,
+                false if quick.len() < quick_avail => &mut quick,
+                _ => continue,
+            };
+
+            stored.queued = true;
+            claimed.push(stored.submission.clone());
+        }
+
+        Ok((precise, quick))
+    }
+    async fn reclaim_expired_submissions(&self, limit: usize) -> Result<Vec<Submission>, DbError> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .submissions
+            .values_mut()
+            .filter(|stored| stored.lease_expired)
+            .take(limit)
+            .map(|stored| {
+                stored.lease_expired = false;
+                stored.submission.clone()
+            })
+            .collect())
+    }
+    async fn claim_submission(&self, id: i32) -> Result<Option<Submission>, DbError> {
+        let mut state = self.state.lock().unwrap();
+        let Some(stored) = state.submissions.get_mut(&id) else {
+            return Ok(None);
+        };
+
+        stored.queued = true;
+        stored.submission.state = SubmissionState::InProgress;
+        stored.submission.generation += 1;
+        Ok(Some(stored.submission.clone()))
+    }
+    async fn cancel_submission(&self, id: i32) -> Result<(), DbError> {
+        if let Some(stored) = self.state.lock().unwrap().submissions.get_mut(&id) {
+            stored.submission.state = SubmissionState::Done;
+            stored.submission.extra = Some("cancelled".to_string());
+        }
+        Ok(())
+    }
+    async fn renew_claims(&self, _ids: Vec<i32>) -> Result<(), DbError> {
+        Ok(())
+    }
+    async fn list_testcase(&self, problem_no: i32) -> Result<Vec<TestCase>, DbError> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .testcases
+            .values()
+            .filter(|testcase| testcase.problem_id == problem_no)
+            .cloned()
+            .collect())
+    }
+    async fn contest_window(&self) -> Result<ContestWindow, DbError> {
+        Ok(self.state.lock().unwrap().contest.clone())
+    }
+    async fn upsert_testcase_judge(
+        &self,
+        submission_id: i32,
+        generation: u32,
+        testcase_id: i32,
+        attempt: u32,
+        result: &TestCaseJudgeResult,
+        result_inner: &TestCaseJudgeResultInner,
+    ) -> Result<(), DbError> {
+        let mut result = result.clone();
+        result.submit_id = submission_id;
+        result.testcase_id = testcase_id;
+
+        self.state.lock().unwrap().testcase_judge.insert(
+            (submission_id, testcase_id, attempt),
+            (generation, (attempt, result, result_inner.clone())),
+        );
+        Ok(())
+    }
+    async fn list_testcase_judge(
+        &self,
+        submission_id: i32,
+        generation: u32,
+    ) -> Result<Vec<TestCaseRun>, DbError> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .testcase_judge
+            .range((submission_id, i32::MIN, 0)..=(submission_id, i32::MAX, u32::MAX))
+            .filter(|(_, (g, _))| *g == generation)
+            .map(|(_, (_, run))| run.clone())
+            .collect())
+    }
+    async fn finish_submission(
+        &self,
+        submission: &Submission,
+        verdict: &Verdict,
+        runs: &[TestCaseRun],
+    ) -> Result<Finished, DbError> {
+        let mut state = self.state.lock().unwrap();
+        if state
+            .submissions
+            .get(&submission.id)
+            .is_some_and(|stored| stored.submission.generation != submission.generation)
+        {
+            return Ok(Finished::NotOwner);
+        }
+
+        for (attempt, result, result_inner) in runs {
+            let mut result = result.clone();
+            result.submit_id = submission.id;
+            state.testcase_judge.insert(
+                (submission.id, result.testcase_id, *attempt),
+                (
+                    submission.generation,
+                    (*attempt, result, result_inner.clone()),
+                ),
+            );
+        }
+        if let Some(stored) = state.submissions.get_mut(&submission.id) {
+            stored.submission.state = SubmissionState::Done;
+            stored.submission.result = Some(if verdict.is_correct {
+                SubmissionResult::Correct
+            } else {
+                SubmissionResult::Wrong
+            });
+            stored.submission.extra = Some(verdict.extra.clone());
+            stored.submission.memory = Some(verdict.memory as i32);
+            stored.submission.runtime = Some(verdict.runtime as i32);
+            stored.needs_review |= verdict.needs_review;
+        }
+        state.finalized.push(submission.id);
+        Ok(Finished::Saved)
+    }
+    async fn schema_version(&self) -> Result<u32, DbError> {
+        Ok(migrate::VERSION)
+    }
+    async fn migrate(&self) -> Result<Vec<&'static Migration>, DbError> {
+        Ok(Vec::new())
+    }
+}
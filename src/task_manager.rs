@@ -1,6 +1,21 @@
-use std::collections::HashMap;
-
-use crate::{db, types::*};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use crate::{
+    admin::{RequeueView, SubmissionView},
+    clock::Clock,
+    config::{self, VerificationConfig},
+    db::DbError,
+    events::{self, EventTX, JudgeEvent},
+    metrics,
+    queue::{PriorityClass, QueuedTask, TaskQueue},
+    store::{Finished, JudgeStore, Verdict},
+    types::*,
+    webhook::{WebhookPayload, WebhookTX},
+};
+
+/// (attempt, 결과)
+pub type TestCaseRun = (u32, TestCaseJudgeResult, TestCaseJudgeResultInner);
 
 #[derive(Debug, Clone)]
 enum JudgeState {
@@ -14,20 +29,34 @@ enum JudgeAction {
     /// (result, extra, runtime, memory)
     End(bool, String, usize, usize),
     AddPreciseTestcase(Vec<TestCase>),
-    /// (priority, testcases)
-    AddQuickTestcase(bool, Vec<TestCase>),
+    AddQuickTestcase(Vec<TestCase>),
     // listener 쪽에서 300초 hard-limit이 있어서 문제 없을듯
     // Cancel(Submission, TestCase),
 }
 
 #[derive(Clone, Debug)]
+#[allow(dead_code)]
 struct JudgeInfo {
     submission: Submission,
+    class: PriorityClass,
     testcase_public: Vec<TestCase>,
     testcase_private: Vec<TestCase>,
 
     testcase_result: HashMap<i32, (TestCaseJudgeResult, TestCaseJudgeResultInner)>,
 
+    /// 교차 검증하는 제출인지 (정밀 채점 + 설정에서 켜져 있을 때)
+    is_verified: bool,
+    /// 교차 검증 중인 테스트케이스의 실행 결과. 판정이 확정되면 `testcase_result`로 들어감
+    testcase_runs: HashMap<i32, Vec<TestCaseRun>>,
+    /// 테스트케이스별로 결과를 낸 워커 (교차 검증시 같은 워커에서 두 번 돌지 않도록)
+    testcase_workers: HashMap<i32, Vec<usize>>,
+    /// 교차 검증 결과가 갈려서 사람이 확인해야 함
+    needs_review: bool,
+    /// (testcase_id, attempt)별로 반영한 실행 결과. 최종 판정과 같은 트랜잭션으로 저장함
+    runs: BTreeMap<(i32, u32), TestCaseRun>,
+    /// 워커 문제로 다시 큐에 넣은 기록 (testcase_id, attempt, 사유)
+    requeue_reasons: Vec<(i32, u32, String)>,
+
     testcase_public_passed: TestCaseJudgeResultInner,
     testcase_private_passed: TestCaseJudgeResultInner,
 
@@ -36,17 +65,30 @@ struct JudgeInfo {
     state: JudgeState,
 }
 impl JudgeInfo {
-    pub fn new(submission: Submission, testcase_of_problem: Vec<TestCase>) -> Self {
+    pub fn new(
+        submission: Submission,
+        class: PriorityClass,
+        testcase_of_problem: Vec<TestCase>,
+        is_verified: bool,
+    ) -> Self {
         let (testcase_pub, testcase_priv) = testcase_of_problem
             .into_iter()
             .partition(|testcase| testcase.is_public);
 
         Self {
             submission,
+            class,
             testcase_public: testcase_pub,
             testcase_private: testcase_priv,
             testcase_result: HashMap::new(),
 
+            is_verified,
+            testcase_runs: HashMap::new(),
+            testcase_workers: HashMap::new(),
+            needs_review: false,
+            runs: BTreeMap::new(),
+            requeue_reasons: vec![],
+
             testcase_public_passed: TestCaseJudgeResultInner::NotYetDone,
             testcase_private_passed: TestCaseJudgeResultInner::NotYetDone,
 
@@ -56,15 +98,48 @@ impl JudgeInfo {
         }
     }
 
+    /// 이미 결과가 있는지 (복구된 제출에서 이미 채점한 테스트케이스는 다시 큐에 넣지 않음)
+    fn has_run(&self, testcase_id: i32, attempt: u32) -> bool {
+        self.testcase_result.contains_key(&testcase_id)
+            || self
+                .testcase_runs
+                .get(&testcase_id)
+                .is_some_and(|runs| runs.iter().any(|(a, _, _)| *a == attempt))
+    }
+
+    fn testcase_event(
+        &self,
+        result: &TestCaseJudgeResult,
+        result_inner: &TestCaseJudgeResultInner,
+    ) -> JudgeEvent {
+        JudgeEvent::Testcase {
+            submission_id: self.submission.id,
+            stud_id: self.submission.stud_id,
+            testcase_id: result.testcase_id,
+            is_public: self
+                .testcase_public
+                .iter()
+                .any(|t| t.id == result.testcase_id),
+            is_passed: matches!(result_inner, TestCaseJudgeResultInner::Accepted),
+            verdict: result_inner.to_string(),
+            runtime: result.runtime,
+            memory: result.memory,
+        }
+    }
+
+    /// 이 테스트케이스를 두 워커에서 돌려서 비교해야 하는지 (비공개 테스트케이스만)
+    fn is_verified_testcase(&self, testcase_id: i32) -> bool {
+        self.is_verified && self.testcase_private.iter().any(|t| t.id == testcase_id)
+    }
+
     pub fn process(&mut self) -> JudgeAction {
         // println!("** process");
 
         match (&self.state, self.submission.run_type) {
             (JudgeState::Inqueue, _) => {
                 self.state = JudgeState::InPublic;
-                let is_priority = self.submission.run_type == SubmissionType::Precise;
 
-                JudgeAction::AddQuickTestcase(is_priority, self.testcase_public.clone())
+                JudgeAction::AddQuickTestcase(self.testcase_public.clone())
             }
             (JudgeState::InPublic, _) => {
                 //   // println!(
@@ -161,113 +236,519 @@ impl JudgeInfo {
     }
 }
 
-pub struct TaskManager {
-    pub task_precise: Vec<(Submission, TestCase)>,
-    pub task_quick: Vec<(Submission, TestCase)>,
+pub struct TaskManager<S: JudgeStore> {
+    pub task_precise: TaskQueue,
+    pub task_quick: TaskQueue,
+
+    /// 제출 번호 순으로 처리하도록 (먼저 들어온 제출의 테스트케이스가 큐에서도 앞에 섬)
+    submissions: BTreeMap<i32, JudgeInfo>,
+
+    /// 제출이 대회 중인지 구분할 때 사용. 새 제출을 가져올 때마다 갱신
+    pub contest: ContestWindow,
 
-    submissions: HashMap<i32, JudgeInfo>,
+    store: Arc<S>,
+    events: EventTX,
+    webhooks: WebhookTX,
 }
 
-impl TaskManager {
-    pub fn new() -> TaskManager {
+impl<S: JudgeStore> TaskManager<S> {
+    pub fn new(
+        clock: Arc<dyn Clock>,
+        store: Arc<S>,
+        events: EventTX,
+        webhooks: WebhookTX,
+    ) -> TaskManager<S> {
+        let scheduler = &config::get().scheduler;
+
         TaskManager {
-            task_precise: Vec::with_capacity(128),
-            task_quick: Vec::with_capacity(128),
+            task_precise: TaskQueue::new(
+                clock.clone(),
+                scheduler.aging_step(),
+                scheduler.max_queue_wait(),
+            ),
+            task_quick: TaskQueue::new(clock, scheduler.aging_step(), scheduler.max_queue_wait()),
+
+            submissions: BTreeMap::new(),
+
+            contest: ContestWindow::default(),
 
-            submissions: HashMap::<i32, JudgeInfo>::new(),
+            store,
+            events,
+            webhooks,
         }
     }
 
-    pub async fn add_submissions(&mut self, submission: Submission) {
-        let testcase = self.list_testcase(submission.problem_no).await;
-
-        println!("testcase {} -> {:?}", submission.id, testcase);
-        let judge = JudgeInfo::new(submission, testcase);
+    /// 테스트케이스를 읽지 못하면 추가하지 않음
+    pub async fn add_submissions(&mut self, submission: Submission) -> Result<(), DbError> {
+        let testcase = self.store.list_testcase(submission.problem_no).await?;
+
+        let class = PriorityClass::of(&submission, &self.contest);
+        let is_verified = config::get().verification.enabled && submission.is_precise();
+        tracing::info!(
+            submission_id = submission.id,
+            stud_id = submission.stud_id,
+            problem_no = submission.problem_no,
+            testcases = testcase.len(),
+            class = ?class,
+            is_verified,
+            "submission added"
+        );
+        events::publish(
+            &self.events,
+            JudgeEvent::Queued {
+                submission_id: submission.id,
+                stud_id: submission.stud_id,
+            },
+        );
+        let judge = JudgeInfo::new(submission, class, testcase, is_verified);
 
         // eprintln!("add test {:?}", judge);
         self.submissions.insert(judge.submission.id, judge);
+        Ok(())
     }
+    /// `generation`: 작업을 배정할 때의 제출 `generation`
     pub async fn add_result(
         &mut self,
         submission_id: i32,
+        generation: u32,
+        channel_id: usize,
+        attempt: u32,
         result: TestCaseJudgeResult,
         result_inner: TestCaseJudgeResultInner,
     ) {
-        db::insert_testcase_judge(submission_id, result.testcase_id, &result, &result_inner).await;
+        // 취소됐거나 이미 끝난 제출, 그 사이에 재채점된 제출의 결과는 반영하지도 저장하지도 않음
+        if !self.is_current(submission_id, generation) {
+            tracing::debug!(
+                submission_id,
+                generation,
+                testcase_id = result.testcase_id,
+                "stale result dropped"
+            );
+            return;
+        }
 
-        if let Some(judge) = self.submissions.get_mut(&submission_id) {
-            judge
-                .testcase_result
-                .insert(result.testcase_id, (result, result_inner));
+        // 저장하지 못해도 판정에는 반영함. 최종 판정을 남길 때 같이 저장되고,
+        // 그 전에 코디네이터가 죽으면 그 테스트케이스만 다시 채점됨
+        if let Err(e) = self
+            .store
+            .upsert_testcase_judge(
+                submission_id,
+                generation,
+                result.testcase_id,
+                attempt,
+                &result,
+                &result_inner,
+            )
+            .await
+        {
+            tracing::error!(
+                submission_id,
+                testcase_id = result.testcase_id,
+                attempt,
+                error = %e,
+                "cannot save testcase result"
+            );
+        }
+
+        self.apply_result(
+            submission_id,
+            Some(channel_id),
+            attempt,
+            result,
+            result_inner,
+        );
+    }
+
+    /// 코디네이터가 죽어서 다시 가져온 제출. 이미 DB에 있는 테스트케이스 결과는 그대로 쓰고 나머지만 채점함.
+    /// 재채점 전 (`generation`이 다른) 결과는 쓰지 않음
+    pub async fn add_recovered_submission(
+        &mut self,
+        submission: Submission,
+    ) -> Result<(), DbError> {
+        let submission_id = submission.id;
+        let generation = submission.generation;
+        // 그 사이에 재채점된 제출이면 이전 채점의 작업이 남아 있으므로 먼저 뺌
+        self.cancel(submission_id);
+        self.add_submissions(submission).await?;
+
+        // 이전 결과를 못 읽으면 처음부터 다시 채점함
+        let results = self
+            .store
+            .list_testcase_judge(submission_id, generation)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!(submission_id, error = %e, "cannot read previous results, rejudging all testcases");
+                Vec::new()
+            });
+        tracing::info!(
+            submission_id,
+            reused = results.len(),
+            "recovered submission"
+        );
+
+        for (attempt, result, result_inner) in results {
+            self.apply_result(submission_id, None, attempt, result, result_inner);
         }
+        Ok(())
     }
 
-    pub fn force_rejudge(&mut self, submission: Submission, testcase: TestCase) {
-        // println!("task quick queue: {:?}", self.task_quick);
+    /// `channel_id`: 결과를 낸 워커. 복구한 결과는 어느 워커인지 모름
+    fn apply_result(
+        &mut self,
+        submission_id: i32,
+        channel_id: Option<usize>,
+        attempt: u32,
+        result: TestCaseJudgeResult,
+        result_inner: TestCaseJudgeResultInner,
+    ) {
+        let Some(judge) = self.submissions.get_mut(&submission_id) else {
+            return;
+        };
+        let testcase_id = result.testcase_id;
+
+        if !judge.is_verified_testcase(testcase_id) {
+            judge.runs.insert(
+                (testcase_id, attempt),
+                (attempt, result.clone(), result_inner.clone()),
+            );
+            events::publish(&self.events, judge.testcase_event(&result, &result_inner));
+            judge
+                .testcase_result
+                .insert(testcase_id, (result, result_inner));
+            return;
+        }
 
-        // 이미 큐에 있으면 패스함
-        match testcase.is_public {
-            false => {
-                if self
-                    .task_precise
+        let runs = judge.testcase_runs.entry(testcase_id).or_default();
+        if runs.iter().any(|(a, _, _)| *a == attempt) {
+            return;
+        }
+        runs.push((attempt, result.clone(), result_inner.clone()));
+        judge
+            .testcase_workers
+            .entry(testcase_id)
+            .or_default()
+            .extend(channel_id);
+        let runs = &judge.testcase_runs[&testcase_id];
+        judge
+            .runs
+            .insert((testcase_id, attempt), (attempt, result, result_inner));
+
+        let verification = &config::get().verification;
+        let decided = match compare_runs(runs, verification) {
+            RunComparison::Pending => return,
+            RunComparison::Agreed(idx) => runs[idx].clone(),
+            RunComparison::Disagreed if runs.len() < 3 && verification.tiebreak => {
+                tracing::warn!(
+                    submission_id,
+                    testcase_id,
+                    "verification disagreed, running tiebreak"
+                );
+
+                let testcase = judge
+                    .testcase_private
                     .iter()
-                    .any(|(s, t)| s.id == submission.id && t.id == testcase.id)
-                {
-                    return;
-                }
-                self.task_precise.insert(0, (submission, testcase))
+                    .find(|t| t.id == testcase_id)
+                    .cloned()
+                    .unwrap();
+                self.task_precise
+                    .push(judge.submission.clone(), testcase, judge.class, 2);
+                return;
             }
-            true => {
-                if self
-                    .task_quick
-                    .iter()
-                    .any(|(s, t)| s.id == submission.id && t.id == testcase.id)
-                {
-                    return;
-                }
-                self.task_quick.insert(0, (submission, testcase))
+            RunComparison::Disagreed => {
+                tracing::warn!(
+                    submission_id,
+                    testcase_id,
+                    "verification disagreed, flagged for review"
+                );
+                judge.needs_review = true;
+
+                // 결과가 갈리면 더 나쁜 판정을 씀
+                runs.iter()
+                    .max_by_key(|(_, _, inner)| inner.clone())
+                    .cloned()
+                    .unwrap()
             }
+        };
+
+        let (_, result, result_inner) = decided;
+        events::publish(&self.events, judge.testcase_event(&result, &result_inner));
+        judge
+            .testcase_result
+            .insert(testcase_id, (result, result_inner));
+    }
+
+    /// 채점 중인 제출이고 `generation`이 지금 채점의 것인지
+    pub fn is_current(&self, submission_id: i32, generation: u32) -> bool {
+        self.submissions
+            .get(&submission_id)
+            .is_some_and(|judge| judge.submission.generation == generation)
+    }
+
+    /// 채점 중인 제출 (lease 갱신용)
+    pub fn submission_ids(&self) -> Vec<i32> {
+        self.submissions.keys().copied().collect()
+    }
+
+    /// 관리자가 요청한 재채점. 채점 중이었으면 처음부터 다시 함
+    pub async fn add_rejudge(&mut self, submission: Submission) -> Result<(), DbError> {
+        let submission_id = submission.id;
+        self.cancel(submission_id);
+        self.add_submissions(submission).await?;
+        metrics::get().count_rejudge("admin_submission");
+
+        if let Some(judge) = self.submissions.get_mut(&submission_id) {
+            judge.class = PriorityClass::AdminRejudge;
+        }
+        Ok(())
+    }
+
+    /// 채점 중인 제출을 큐와 함께 뺌. 이미 워커에 배정된 작업의 결과는 무시됨
+    pub fn cancel(&mut self, submission_id: i32) -> bool {
+        self.task_precise.remove_submission(submission_id);
+        self.task_quick.remove_submission(submission_id);
+
+        self.submissions.remove(&submission_id).is_some()
+    }
+
+    pub fn submission_views(&self) -> Vec<SubmissionView> {
+        let mut views: Vec<_> = self
+            .submissions
+            .values()
+            .map(|judge| SubmissionView {
+                submission_id: judge.submission.id,
+                stud_id: judge.submission.stud_id,
+                problem_no: judge.submission.problem_no,
+                class: judge.class,
+                state: format!("{:?}", judge.state),
+                testcases_total: judge.testcase_public.len()
+                    + if judge.submission.is_precise() {
+                        judge.testcase_private.len()
+                    } else {
+                        0
+                    },
+                testcases_done: judge.testcase_result.len(),
+                is_verified: judge.is_verified,
+                needs_review: judge.needs_review,
+                requeues: judge
+                    .requeue_reasons
+                    .iter()
+                    .map(|(testcase_id, attempt, reason)| RequeueView {
+                        testcase_id: *testcase_id,
+                        attempt: *attempt,
+                        reason: reason.clone(),
+                    })
+                    .collect(),
+            })
+            .collect();
+        views.sort_by_key(|view| view.submission_id);
+
+        views
+    }
+
+    /// 교차 검증 중인 테스트케이스면 이미 배정됐던 워커는 제외해야 함
+    pub fn excluded_channels(&self, task: &QueuedTask) -> Vec<usize> {
+        match self.submissions.get(&task.submission.id) {
+            Some(judge) if judge.is_verified_testcase(task.testcase.id) => judge
+                .testcase_workers
+                .get(&task.testcase.id)
+                .cloned()
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// 제외할 워커밖에 없어서 같은 워커에 다시 배정함. 교차 검증이 의미가 없으므로 사람이 확인해야 함
+    pub fn record_unverified_dispatch(&mut self, task: &QueuedTask, channel_id: usize) {
+        tracing::warn!(
+            submission_id = task.submission.id,
+            testcase_id = task.testcase.id,
+            attempt = task.attempt,
+            channel_id,
+            "no other precise worker for verification, flagged for review"
+        );
+
+        if let Some(judge) = self.submissions.get_mut(&task.submission.id) {
+            judge.needs_review = true;
+        }
+    }
+
+    /// 워커 문제로 테스트케이스를 다시 큐에 넣는 사유를 남김
+    pub fn record_requeue(
+        &mut self,
+        submission_id: i32,
+        testcase_id: i32,
+        attempt: u32,
+        reason: String,
+    ) {
+        tracing::warn!(
+            submission_id,
+            testcase_id,
+            attempt,
+            %reason,
+            "requeue"
+        );
+
+        if let Some(judge) = self.submissions.get_mut(&submission_id) {
+            judge.requeue_reasons.push((testcase_id, attempt, reason));
+        }
+    }
+
+    /// 테스트케이스 하나를 다시 큐에 넣음
+    ///
+    /// 워커가 거절/종료한 경우는 `PriorityClass::Retry`, 관리자가 요청한 경우는 `PriorityClass::AdminRejudge`
+    pub fn force_rejudge(
+        &mut self,
+        submission: Submission,
+        testcase: TestCase,
+        class: PriorityClass,
+        attempt: u32,
+    ) {
+        // 취소됐거나 끝난 제출, 그 사이에 재채점된 제출의 작업은 다시 넣지 않음 (결과도 버려짐)
+        if !self.is_current(submission.id, submission.generation) {
+            return;
         }
 
-        // println!("--> task quick queue {:?}", self.task_quick);
+        let queue = match testcase.is_public {
+            false => &mut self.task_precise,
+            true => &mut self.task_quick,
+        };
+
+        // 이미 큐에 있으면 패스함
+        if queue.contains(submission.id, testcase.id, attempt) {
+            return;
+        }
+        tracing::debug!(
+            submission_id = submission.id,
+            testcase_id = testcase.id,
+            attempt,
+            class = ?class,
+            "testcase requeued"
+        );
+        queue.push(submission, testcase, class, attempt);
     }
 
     pub async fn process(&mut self) {
-        let mut actions: Vec<(Submission, JudgeAction)> =
+        let mut actions: Vec<(Submission, PriorityClass, JudgeAction)> =
             Vec::with_capacity(self.submissions.len());
 
         for judge in self.submissions.values_mut() {
-            actions.push((judge.submission.clone(), judge.process()));
+            actions.push((judge.submission.clone(), judge.class, judge.process()));
         }
 
-        for (sub, act) in actions {
-            if !(self.process_judge(&sub, act).await) {
+        for (sub, class, act) in actions {
+            if !(self.process_judge(&sub, class, act).await) {
                 self.submissions.remove(&sub.id);
             }
         }
     }
 
-    async fn process_judge(&mut self, submission: &Submission, judge: JudgeAction) -> bool {
+    async fn process_judge(
+        &mut self,
+        submission: &Submission,
+        class: PriorityClass,
+        judge: JudgeAction,
+    ) -> bool {
         match judge {
-            JudgeAction::AddPreciseTestcase(mut testcases) => {
-                self.task_precise
-                    .extend(testcases.drain(..).map(|t| (submission.clone(), t)));
+            JudgeAction::AddPreciseTestcase(testcases) => {
+                let Some(judge) = self.submissions.get(&submission.id) else {
+                    return true;
+                };
+                let attempts = if judge.is_verified { 2 } else { 1 };
+
+                for testcase in testcases {
+                    for attempt in 0..attempts {
+                        if judge.has_run(testcase.id, attempt) {
+                            continue;
+                        }
+                        self.task_precise.push(
+                            submission.clone(),
+                            testcase.clone(),
+                            class,
+                            attempt,
+                        );
+                    }
+                }
             }
-            JudgeAction::AddQuickTestcase(is_priority, mut testcases) => {
-                if is_priority {
-                    testcases.reverse();
-
-                    testcases
-                        .drain(..)
-                        .for_each(|t| self.task_quick.insert(0, (submission.clone(), t)));
-                } else {
-                    self.task_quick
-                        .extend(testcases.drain(..).map(|t| (submission.clone(), t)));
+            JudgeAction::AddQuickTestcase(testcases) => {
+                let Some(judge) = self.submissions.get(&submission.id) else {
+                    return true;
+                };
+
+                for testcase in testcases {
+                    if judge.has_run(testcase.id, 0) {
+                        continue;
+                    }
+                    self.task_quick.push(submission.clone(), testcase, class, 0);
                 }
             }
             JudgeAction::End(result, msg, runtime, memory) => {
-                db::update_submission_end(submission, result, msg, memory, runtime).await;
+                let Some(judge) = self.submissions.get(&submission.id) else {
+                    return false;
+                };
+                let verdict = Verdict {
+                    is_correct: result,
+                    extra: msg.clone(),
+                    runtime,
+                    memory,
+                    needs_review: judge.needs_review,
+                };
+                let runs: Vec<_> = judge.runs.values().cloned().collect();
+
+                // 저장하지 못하면 끝난 것으로 알리지 않음. lease가 만료되면 reaper가 다시 가져가서
+                // 저장된 테스트케이스 결과로 다시 판정함.
+                // 다른 코디네이터가 가져간 제출이면 그쪽에서 판정을 내고 알림
+                match self
+                    .store
+                    .finish_submission(submission, &verdict, &runs)
+                    .await
+                {
+                    Ok(Finished::Saved) => (),
+                    Ok(Finished::NotOwner) => {
+                        tracing::warn!(
+                            submission_id = submission.id,
+                            "submission is claimed by another coordinator, verdict not saved"
+                        );
+                        return false;
+                    }
+                    Err(e) => {
+                        tracing::error!(submission_id = submission.id, error = %e, "cannot save verdict");
+                        return false;
+                    }
+                }
 
+                tracing::info!(
+                    submission_id = submission.id,
+                    is_correct = result,
+                    verdict = %msg,
+                    runtime,
+                    memory,
+                    "submission finished"
+                );
+                events::publish(
+                    &self.events,
+                    JudgeEvent::Finished {
+                        submission_id: submission.id,
+                        stud_id: submission.stud_id,
+                        is_correct: result,
+                        verdict: msg.clone(),
+                        runtime,
+                        memory,
+                    },
+                );
+                // spool 파일로 저장한 다음에 넘어감
+                self.webhooks
+                    .send(WebhookPayload {
+                        event: "submission.finished",
+                        submission_id: submission.id,
+                        stud_id: submission.stud_id,
+                        problem_no: submission.problem_no,
+                        is_correct: result,
+                        verdict: msg,
+                        runtime,
+                        memory,
+                        finished_at: chrono::Local::now().to_rfc3339(),
+                    })
+                    .await;
                 return false;
             }
             JudgeAction::NoOp => (),
@@ -276,8 +757,41 @@ impl TaskManager {
 
         true
     }
+}
+
+enum RunComparison {
+    /// 아직 비교할 결과가 모자람
+    Pending,
+    /// 일치하는 두 결과 중 실행 시간이 더 긴 쪽
+    Agreed(usize),
+    Disagreed,
+}
+
+/// 판정이 같고 실행 시간이 허용 범위 안인 두 결과가 있으면 일치한 것으로 봄
+fn compare_runs(runs: &[TestCaseRun], verification: &VerificationConfig) -> RunComparison {
+    if runs.len() < 2 {
+        return RunComparison::Pending;
+    }
 
-    async fn list_testcase(&mut self, problem_no: i32) -> Vec<TestCase> {
-        db::list_testcase(problem_no).await
+    for i in 0..runs.len() {
+        for j in (i + 1)..runs.len() {
+            let (_, result_a, inner_a) = &runs[i];
+            let (_, result_b, inner_b) = &runs[j];
+            if inner_a != inner_b {
+                continue;
+            }
+
+            let runtime_a = result_a.runtime.unwrap_or(0);
+            let runtime_b = result_b.runtime.unwrap_or(0);
+            let tolerance = ((runtime_a.max(runtime_b) as f64 * verification.runtime_tolerance)
+                as usize)
+                .max(verification.runtime_tolerance_ms);
+
+            if runtime_a.abs_diff(runtime_b) <= tolerance {
+                return RunComparison::Agreed(if runtime_a >= runtime_b { i } else { j });
+            }
+        }
     }
+
+    RunComparison::Disagreed
 }
@@ -1,29 +1,69 @@
-use chrono::NaiveDateTime;
+use chrono::{NaiveDate, NaiveDateTime};
 use mysql_async::{from_value_opt, FromValueError, Value};
 use mysql_async::{prelude::*, Row};
 
-/*
--- ksjudge.Submit definition
+/// DB 행을 구조체로 옮기지 못함. 컬럼이 없거나 값이 맞지 않을 때
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowError {
+    pub table: &'static str,
+    pub column: &'static str,
+    pub reason: String,
+}
+impl std::fmt::Display for RowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}: {}", self.table, self.column, self.reason)
+    }
+}
+impl std::error::Error for RowError {}
+
+/// 컬럼 하나를 꺼냄. NULL일 수 있는 컬럼은 `Option<T>`로 꺼내야 함
+pub fn column<T: FromValue>(
+    row: &Row,
+    table: &'static str,
+    name: &'static str,
+) -> Result<T, RowError> {
+    match row.get_opt(name) {
+        Some(Ok(value)) => Ok(value),
+        Some(Err(FromValueError(value))) => Err(RowError {
+            table,
+            column: name,
+            reason: format!("unexpected value {:?}", value),
+        }),
+        None => Err(RowError {
+            table,
+            column: name,
+            reason: "missing column".to_string(),
+        }),
+    }
+}
 
-CREATE TABLE `Submit` (
-  `id` int NOT NULL AUTO_INCREMENT,
-  `stud_id` int NOT NULL,
-  `type` char(1) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci DEFAULT NULL COMMENT '0 = 실행, 1 = 제출',
-  `problemNo` int NOT NULL,
-  `lang` varchar(10) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci DEFAULT NULL,
-  `code` text CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci,
-  `state` char(1) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci DEFAULT NULL COMMENT '0 = 채점 중, 1 = 채점 완료',
-  `extra` varchar(255) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci DEFAULT NULL,
-  `result` char(1) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci DEFAULT NULL COMMENT '0 = 정답, 1 = 오답',
-  `code_size` int DEFAULT NULL,
-  `submit_at` datetime DEFAULT NULL,
-  `runtime` int DEFAULT NULL,
-  `memory` int DEFAULT NULL,
-  PRIMARY KEY (`id`),
-  KEY `Submit_FK` (`stud_id`) USING BTREE,
-  KEY `Submit_FK_1` (`problemNo`) USING BTREE
-) ENGINE=InnoDB AUTO_INCREMENT=6 DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci;
-*/
+/// DATETIME 컬럼. text 프로토콜로 읽으면 문자열, binary 프로토콜로 읽으면 `Value::Date`로 옴
+pub fn datetime_column(
+    row: &Row,
+    table: &'static str,
+    name: &'static str,
+) -> Result<NaiveDateTime, RowError> {
+    let value: Value = column(row, table, name)?;
+    let at = match &value {
+        Value::Date(year, month, day, hour, minute, second, micros) => {
+            NaiveDate::from_ymd_opt(*year as i32, *month as u32, *day as u32).and_then(|date| {
+                date.and_hms_micro_opt(*hour as u32, *minute as u32, *second as u32, *micros)
+            })
+        }
+        Value::Bytes(bytes) => std::str::from_utf8(bytes)
+            .ok()
+            .and_then(|v| NaiveDateTime::parse_from_str(v, "%Y-%m-%d %H:%M:%S").ok()),
+        _ => None,
+    };
+
+    at.ok_or_else(|| RowError {
+        table,
+        column: name,
+        reason: format!("invalid datetime {:?}", value),
+    })
+}
+
+// 테이블 정의는 migrations/<백엔드>/ 참고
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct Submission {
     pub id: i32,
@@ -39,6 +79,8 @@ pub struct Submission {
     pub runtime: Option<i32>,
     pub memory: Option<i32>,
     pub score: Option<u32>,
+    /// 관리자 재채점마다 올라가는 번호. 테스트케이스 결과는 같은 번호의 것만 씀
+    pub generation: u32,
 }
 impl Submission {
     pub fn is_precise(&self) -> bool {
@@ -47,30 +89,26 @@ impl Submission {
 }
 
 impl TryFrom<Row> for Submission {
-    type Error = ();
+    type Error = RowError;
 
     fn try_from(row: Row) -> Result<Self, Self::Error> {
+        const TABLE: &str = "Submit";
+
         Ok(Submission {
-            id: row.get("id").ok_or(())?,
-            stud_id: row.get("stud_id").ok_or(())?,
-            run_type: match row.get("type").ok_or(())? {
-                0 => crate::types::SubmissionType::Quick,
-                1 => crate::types::SubmissionType::Precise,
-                _ => panic!("Invalid submission type"),
-            },
-            problem_no: row.get("problemNo").ok_or(())?,
-            lang: row.get("lang").ok_or(())?,
-            code: row.get("code").ok_or(())?,
-            state: row.get("state").ok_or(())?,
-            extra: row.get("extra").ok_or(())?,
-            result: row.get("result").ok_or(())?,
-            submit_at: row
-                .get("submit_at")
-                .and_then(|v: String| NaiveDateTime::parse_from_str(&v, "%Y-%m-%d %H:%M:%S").ok())
-                .ok_or(())?,
-            runtime: row.get("runtime").ok_or(())?,
-            memory: row.get("memory").ok_or(())?,
-            score: row.get("score").ok_or(())?,
+            id: column(&row, TABLE, "id")?,
+            stud_id: column(&row, TABLE, "stud_id")?,
+            run_type: column(&row, TABLE, "type")?,
+            problem_no: column(&row, TABLE, "problemNo")?,
+            lang: column(&row, TABLE, "lang")?,
+            code: column(&row, TABLE, "code")?,
+            state: column(&row, TABLE, "state")?,
+            extra: column(&row, TABLE, "extra")?,
+            result: column(&row, TABLE, "result")?,
+            submit_at: datetime_column(&row, TABLE, "submit_at")?,
+            runtime: column(&row, TABLE, "runtime")?,
+            memory: column(&row, TABLE, "memory")?,
+            score: column(&row, TABLE, "score")?,
+            generation: column(&row, TABLE, "generation")?,
         })
     }
 }
@@ -83,18 +121,13 @@ pub enum SubmissionType {
 impl TryFrom<Value> for SubmissionType {
     type Error = FromValueError;
 
+    /// `0 = 실행, 1 = 제출`
     fn try_from(value: Value) -> Result<Self, Self::Error> {
-        let val = match value {
-            mysql_async::Value::Int(val) => val,
-            mysql_async::Value::UInt(val) => val as i64,
-            _ => 0,
-        };
-
-        Ok(match val {
-            0 => SubmissionType::Precise,
-            1 => SubmissionType::Quick,
-            _ => SubmissionType::Precise,
-        })
+        match from_value_opt::<i64>(value.clone())? {
+            0 => Ok(SubmissionType::Quick),
+            1 => Ok(SubmissionType::Precise),
+            _ => Err(FromValueError(value)),
+        }
     }
 }
 impl FromValue for SubmissionType {
@@ -111,18 +144,12 @@ impl TryFrom<Value> for SubmissionState {
     type Error = FromValueError;
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
-        let val = match value {
-            mysql_async::Value::Int(val) => val,
-            mysql_async::Value::UInt(val) => val as i64,
-            _ => 0,
-        };
-
-        Ok(match val {
-            0 => SubmissionState::Submitted,
-            1 => SubmissionState::InProgress,
-            2 => SubmissionState::Done,
-            _ => SubmissionState::Submitted,
-        })
+        match from_value_opt::<i64>(value.clone())? {
+            0 => Ok(SubmissionState::Submitted),
+            1 => Ok(SubmissionState::InProgress),
+            2 => Ok(SubmissionState::Done),
+            _ => Err(FromValueError(value)),
+        }
     }
 }
 impl FromValue for SubmissionState {
@@ -138,17 +165,11 @@ impl TryFrom<Value> for SubmissionResult {
     type Error = FromValueError;
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
-        let val = match value {
-            mysql_async::Value::Int(val) => val,
-            mysql_async::Value::UInt(val) => val as i64,
-            _ => 0,
-        };
-
-        Ok(match val {
-            0 => SubmissionResult::Correct,
-            1 => SubmissionResult::Wrong,
-            _ => SubmissionResult::Correct,
-        })
+        match from_value_opt::<i64>(value.clone())? {
+            0 => Ok(SubmissionResult::Correct),
+            1 => Ok(SubmissionResult::Wrong),
+            _ => Err(FromValueError(value)),
+        }
     }
 }
 impl FromValue for SubmissionResult {
@@ -216,19 +237,22 @@ impl From<SubmissionLanguage> for String {
     }
 }
 
-/*
-CREATE TABLE `Testcase` (
-  `id` int NOT NULL AUTO_INCREMENT,
-  `input` text CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci,
-  `output` text CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci,
-  `problem_id` int NOT NULL,
-  `isPublic` tinyint NOT NULL DEFAULT '1' COMMENT '0 = 공개, 1 = 비공개',
-  `runtime` int DEFAULT NULL COMMENT COMMENT 'ms 단위',
-  `memory_limit` int DEFAULT NULL,
-  PRIMARY KEY (`id`),
-  KEY `Testcase_FK_1` (`problem_id`) USING BTREE
-) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci;
-*/
+/// config 테이블의 `START_AT` ~ `END_AT`. `START_AT`이 없으면 대회 중이 아닌 것으로 봄
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContestWindow {
+    pub start_at: Option<NaiveDateTime>,
+    pub end_at: Option<NaiveDateTime>,
+}
+impl ContestWindow {
+    pub fn contains(&self, at: &NaiveDateTime) -> bool {
+        match (self.start_at, self.end_at) {
+            (None, _) => false,
+            (Some(start_at), None) => start_at <= *at,
+            (Some(start_at), Some(end_at)) => start_at <= *at && *at <= end_at,
+        }
+    }
+}
+
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct TestCase {
     pub id: i32,
@@ -236,41 +260,30 @@ pub struct TestCase {
     pub output: String,
     pub problem_id: i32,
     pub is_public: bool,
+    /// 시간 제한 (ms). 없으면 `limits.time_limit_ms`
     pub runtime: Option<usize>,
     pub memory_limit: Option<usize>,
     pub is_decimal_mode: i32,
 }
 impl TryFrom<Row> for TestCase {
-    type Error = ();
+    type Error = RowError;
 
     fn try_from(row: Row) -> Result<Self, Self::Error> {
+        const TABLE: &str = "Testcase";
+
         Ok(TestCase {
-            id: row.get("id").ok_or(())?,
-            input: row.get("input").ok_or(())?,
-            output: row.get("output").ok_or(())?,
-            problem_id: row.get("problem_id").ok_or(())?,
-            is_public: row.get("isPublic").ok_or(())?,
-            runtime: row.get("runtime").ok_or(())?,
-            memory_limit: row.get("memory_limit").ok_or(())?,
-            is_decimal_mode: row.get("is_decimal_mode").ok_or(())?,
+            id: column(&row, TABLE, "id")?,
+            input: column(&row, TABLE, "input")?,
+            output: column(&row, TABLE, "output")?,
+            problem_id: column(&row, TABLE, "problem_id")?,
+            is_public: column(&row, TABLE, "isPublic")?,
+            runtime: column(&row, TABLE, "runtime")?,
+            memory_limit: column(&row, TABLE, "memory_limit")?,
+            is_decimal_mode: column(&row, TABLE, "is_decimal_mode")?,
         })
     }
 }
 
-/*
--- ksjudge.Testcase_judge definition
-
-CREATE TABLE `Testcase_judge` (
-  `id` int NOT NULL AUTO_INCREMENT,
-  `submit_id` int NOT NULL,
-  `output` text CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci,
-  `runtime` int DEFAULT NULL,
-  `result` tinyint DEFAULT NULL COMMENT '0 = 성공, 1 = 실패',
-  `compile_log` text,
-  PRIMARY KEY (`id`),
-  KEY `Testcase_judge_FK` (`submit_id`) USING BTREE
-) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci;
-*/
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct TestCaseJudgeResult {
     pub id: Option<i32>,
@@ -284,6 +297,7 @@ pub struct TestCaseJudgeResult {
     pub judge_server_id: String,
 }
 impl TestCaseJudgeResult {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         submit_id: i32,
         testcase_id: i32,
@@ -310,6 +324,25 @@ impl TestCaseJudgeResult {
         self.result
     }
 }
+impl TryFrom<Row> for TestCaseJudgeResult {
+    type Error = RowError;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        const TABLE: &str = "Testcase_judge";
+
+        Ok(TestCaseJudgeResult {
+            id: column(&row, TABLE, "id")?,
+            submit_id: column(&row, TABLE, "submit_id")?,
+            testcase_id: column(&row, TABLE, "testcase_id")?,
+            output: column(&row, TABLE, "output")?,
+            runtime: column(&row, TABLE, "runtime")?,
+            memory: column(&row, TABLE, "memory")?,
+            result: column::<i32>(&row, TABLE, "result")? == 0,
+            compile_log: column(&row, TABLE, "compile_log")?,
+            judge_server_id: column(&row, TABLE, "judge_server_id")?,
+        })
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord)]
 pub enum TestCaseJudgeResultInner {
@@ -322,17 +355,35 @@ pub enum TestCaseJudgeResultInner {
     CompileFailed,
     RuntimeError,
 }
-impl ToString for TestCaseJudgeResultInner {
-    fn to_string(&self) -> String {
-        match self {
-            Self::NotYetDone => "채점 대기중".to_string(),
-            Self::Accepted => "정답".to_string(),
-            Self::WrongAnswer => "잘못된 출력".to_string(),
-            Self::TimeLimitExceeded => "시간 초과".to_string(),
-            Self::MemoryLimitExceeded => "메모리 초과".to_string(),
-            Self::OutputLimitExceeded => "출력 초과".to_string(),
-            Self::CompileFailed => "컴파일 실패".to_string(),
-            Self::RuntimeError => "런타임 오류".to_string(),
+impl std::fmt::Display for TestCaseJudgeResultInner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::NotYetDone => "채점 대기중",
+            Self::Accepted => "정답",
+            Self::WrongAnswer => "잘못된 출력",
+            Self::TimeLimitExceeded => "시간 초과",
+            Self::MemoryLimitExceeded => "메모리 초과",
+            Self::OutputLimitExceeded => "출력 초과",
+            Self::CompileFailed => "컴파일 실패",
+            Self::RuntimeError => "런타임 오류",
+        })
+    }
+}
+/// `Display`로 저장된 문자열 (Testcase_judge.result_extra)을 다시 읽음
+impl std::str::FromStr for TestCaseJudgeResultInner {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "채점 대기중" => Ok(Self::NotYetDone),
+            "정답" => Ok(Self::Accepted),
+            "잘못된 출력" => Ok(Self::WrongAnswer),
+            "시간 초과" => Ok(Self::TimeLimitExceeded),
+            "메모리 초과" => Ok(Self::MemoryLimitExceeded),
+            "출력 초과" => Ok(Self::OutputLimitExceeded),
+            "컴파일 실패" => Ok(Self::CompileFailed),
+            "런타임 오류" => Ok(Self::RuntimeError),
+            _ => Err(()),
         }
     }
 }
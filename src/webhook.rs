@@ -0,0 +1,421 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::sync::mpsc;
+
+use crate::config::{self, WebhookTarget};
+
+/// 채점이 끝났을 때 webhook 대상에게 보내는 본문
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookPayload {
+    /// 항상 `submission.finished`
+    pub event: &'static str,
+    pub submission_id: i32,
+    pub stud_id: i32,
+    pub problem_no: i32,
+    pub is_correct: bool,
+    pub verdict: String,
+    pub runtime: usize,
+    pub memory: usize,
+    /// RFC 3339
+    pub finished_at: String,
+}
+
+/// 매니저가 webhook을 보낼 때 쓰는 쪽. 대상마다 spool 파일로 저장한 다음에 전송 태스크에 넘기므로
+/// `send`가 끝나면 그 사이에 죽어도 재시작할 때 이어서 보냄
+#[derive(Clone)]
+pub struct WebhookTX {
+    spool_dir: PathBuf,
+    targets: Vec<(String, mpsc::UnboundedSender<Delivery>)>,
+}
+impl WebhookTX {
+    pub async fn send(&self, payload: WebhookPayload) {
+        if self.targets.is_empty() {
+            return;
+        }
+        let body = serde_json::to_string(&payload).unwrap();
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+
+        for (target, tx) in &self.targets {
+            let delivery = Delivery {
+                id: format!("{}-{}-{}", payload.submission_id, target, millis),
+                target: target.clone(),
+                body: body.clone(),
+                attempts: 0,
+                next_attempt_at: 0,
+            };
+
+            save(&self.spool_dir, &delivery).await;
+            // 전송 태스크가 없으면 spool 파일만 남고 재시작할 때 보냄
+            let _ = tx.send(delivery);
+        }
+    }
+}
+
+/// 설정된 대상마다 전송 통로를 만듦. 대상이 없으면 `WebhookTX::send`는 아무것도 하지 않음
+pub fn channel() -> (WebhookTX, Webhooks) {
+    let webhooks = &config::get().webhooks;
+    let spool_dir = PathBuf::from(&webhooks.spool_dir);
+    if !webhooks.targets.is_empty() {
+        if let Err(e) = std::fs::create_dir_all(spool_dir.join("failed")) {
+            tracing::error!(spool_dir = %spool_dir.display(), error = %e, "webhooks: cannot create spool dir");
+        }
+    }
+
+    let (senders, receivers) = webhooks
+        .targets
+        .iter()
+        .map(|target| {
+            let (tx, rx) = mpsc::unbounded_channel();
+            ((target.name.clone(), tx), (target.clone(), rx))
+        })
+        .unzip();
+
+    let client = reqwest::Client::builder()
+        .timeout(webhooks.timeout())
+        .build()
+        .unwrap();
+
+    (
+        WebhookTX {
+            spool_dir: spool_dir.clone(),
+            targets: senders,
+        },
+        Webhooks {
+            client,
+            spool_dir,
+            targets: receivers,
+        },
+    )
+}
+
+/// 대상 하나에 보낼 요청. 보내기 전에 spool 파일로 저장하고, 보내면 지움
+#[derive(Debug, Serialize, Deserialize)]
+struct Delivery {
+    id: String,
+    target: String,
+    /// 서명한 그대로 보내야 하므로 직렬화한 문자열로 보관함
+    body: String,
+    attempts: u32,
+    /// unix time (초)
+    next_attempt_at: u64,
+}
+
+/// webhook 전송. 대상마다 태스크를 따로 두어서 응답이 느린 대상이 다른 대상을 막지 않게 하고,
+/// 실패하면 spool 디렉토리에 남겨서 재시도함
+///
+/// 서명: `X-Judge-Signature: sha256=<hex(HMAC-SHA256(secret, "<X-Judge-Timestamp>.<본문>"))>`.
+/// 같은 요청이 두 번 갈 수 있으므로 받는 쪽은 `X-Judge-Delivery`로 중복을 걸러야 함
+pub struct Webhooks {
+    client: reqwest::Client,
+    spool_dir: PathBuf,
+    targets: Vec<(WebhookTarget, mpsc::UnboundedReceiver<Delivery>)>,
+}
+impl Webhooks {
+    pub async fn run(self) {
+        let spooled = resume_spool(&self.spool_dir, |name| {
+            self.targets.iter().any(|(t, _)| t.name == name)
+        })
+        .await;
+        if !spooled.is_empty() {
+            tracing::info!(
+                pending = spooled.len(),
+                "webhooks: resuming spooled deliveries"
+            );
+        }
+
+        let mut queues: Vec<_> = self
+            .targets
+            .into_iter()
+            .map(|(target, rx)| TargetQueue {
+                client: self.client.clone(),
+                spool_dir: self.spool_dir.clone(),
+                target,
+                rx,
+                pending: vec![],
+            })
+            .collect();
+        for delivery in spooled {
+            if let Some(queue) = queues.iter_mut().find(|q| q.target.name == delivery.target) {
+                queue.pending.push(delivery);
+            }
+        }
+
+        futures_util::future::join_all(queues.into_iter().map(TargetQueue::run)).await;
+    }
+}
+
+/// 대상 하나로 가는 요청. 받은 요청은 이미 spool 파일로 저장되어 있음
+struct TargetQueue {
+    client: reqwest::Client,
+    spool_dir: PathBuf,
+    target: WebhookTarget,
+    rx: mpsc::UnboundedReceiver<Delivery>,
+    pending: Vec<Delivery>,
+}
+impl TargetQueue {
+    async fn run(mut self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            tokio::select! {
+                delivery = self.rx.recv() => {
+                    let Some(delivery) = delivery else { return };
+                    self.pending.push(delivery);
+                    self.deliver_due().await;
+                }
+                _ = interval.tick() => {
+                    self.deliver_due().await;
+                }
+            }
+        }
+    }
+
+    async fn deliver_due(&mut self) {
+        let webhooks = &config::get().webhooks;
+        let now = unix_now();
+
+        let mut i = 0;
+        while i < self.pending.len() {
+            let delivery = &mut self.pending[i];
+            if delivery.next_attempt_at > now {
+                i += 1;
+                continue;
+            }
+
+            match send(&self.client, &self.target, delivery).await {
+                Ok(()) => {
+                    tracing::debug!(
+                        delivery = delivery.id,
+                        target = delivery.target,
+                        "webhook delivered"
+                    );
+                    let delivery = self.pending.swap_remove(i);
+                    remove(&self.spool_dir, &delivery).await;
+                }
+                Err(e) => {
+                    delivery.attempts += 1;
+
+                    if delivery.attempts >= webhooks.max_attempts {
+                        tracing::error!(delivery = delivery.id, target = delivery.target, attempts = delivery.attempts, error = %e, "webhook failed, giving up");
+                        let delivery = self.pending.swap_remove(i);
+                        move_to_failed(&self.spool_dir, &delivery).await;
+                        continue;
+                    }
+
+                    let backoff = webhooks.backoff(delivery.attempts);
+                    tracing::warn!(delivery = delivery.id, target = delivery.target, attempts = delivery.attempts, retry_in_secs = backoff.as_secs(), error = %e, "webhook failed");
+                    delivery.next_attempt_at = now + backoff.as_secs();
+                    save(&self.spool_dir, delivery).await;
+                    i += 1;
+                }
+            }
+        }
+    }
+}
+
+async fn send(
+    client: &reqwest::Client,
+    target: &WebhookTarget,
+    delivery: &Delivery,
+) -> Result<(), String> {
+    let timestamp = unix_now().to_string();
+    let signature = sign(&target.secret, &timestamp, &delivery.body);
+
+    let response = client
+        .post(&target.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header("X-Judge-Delivery", &delivery.id)
+        .header("X-Judge-Timestamp", &timestamp)
+        .header("X-Judge-Signature", format!("sha256={}", signature))
+        .body(delivery.body.clone())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match response.status() {
+        status if status.is_success() => Ok(()),
+        status => Err(format!("status {}", status)),
+    }
+}
+
+fn sign(secret: &str, timestamp: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn spool_path(spool_dir: &Path, delivery: &Delivery) -> PathBuf {
+    spool_dir.join(format!("{}.json", delivery.id))
+}
+
+async fn load_spool(spool_dir: &Path) -> Vec<Delivery> {
+    let mut deliveries = vec![];
+
+    let Ok(mut entries) = tokio::fs::read_dir(spool_dir).await else {
+        return deliveries;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+
+        match tokio::fs::read_to_string(&path)
+            .await
+            .map(|text| serde_json::from_str(&text))
+        {
+            Ok(Ok(delivery)) => deliveries.push(delivery),
+            _ => tracing::warn!(path = %path.display(), "webhooks: cannot read spool file"),
+        }
+    }
+
+    deliveries
+}
+
+/// spool에 남은 요청을 읽음. 설정에서 빠진 대상에게 보내려던 요청은 더 보내지 않고 `failed`로 옮김
+async fn resume_spool(spool_dir: &Path, is_configured: impl Fn(&str) -> bool) -> Vec<Delivery> {
+    let (spooled, stale): (Vec<_>, Vec<_>) = load_spool(spool_dir)
+        .await
+        .into_iter()
+        .partition(|delivery| is_configured(&delivery.target));
+    for delivery in &stale {
+        tracing::warn!(
+            delivery = delivery.id,
+            target = delivery.target,
+            "webhooks: target no longer configured"
+        );
+        move_to_failed(spool_dir, delivery).await;
+    }
+
+    spooled
+}
+
+/// 쓰다가 죽어도 깨진 파일이 남지 않도록 임시 파일에 쓰고 옮김
+async fn save(spool_dir: &Path, delivery: &Delivery) {
+    let path = spool_path(spool_dir, delivery);
+    let tmp = path.with_extension("tmp");
+
+    let result = async {
+        tokio::fs::write(&tmp, serde_json::to_vec(delivery).unwrap()).await?;
+        tokio::fs::rename(&tmp, &path).await
+    }
+    .await;
+    if let Err(e) = result {
+        tracing::error!(delivery = delivery.id, error = %e, "webhooks: cannot write spool file");
+    }
+}
+
+async fn remove(spool_dir: &Path, delivery: &Delivery) {
+    if let Err(e) = tokio::fs::remove_file(spool_path(spool_dir, delivery)).await {
+        tracing::warn!(delivery = delivery.id, error = %e, "webhooks: cannot remove spool file");
+    }
+}
+
+async fn move_to_failed(spool_dir: &Path, delivery: &Delivery) {
+    save(spool_dir, delivery).await;
+
+    let from = spool_path(spool_dir, delivery);
+    let to = spool_path(&spool_dir.join("failed"), delivery);
+    if let Err(e) = tokio::fs::rename(&from, &to).await {
+        tracing::error!(delivery = delivery.id, error = %e, "webhooks: cannot move spool file");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::WebhooksConfig;
+
+    fn delivery(id: &str, target: &str) -> Delivery {
+        Delivery {
+            id: id.to_string(),
+            target: target.to_string(),
+            body: format!("{{\"id\":{:?}}}", id),
+            attempts: 2,
+            next_attempt_at: 1_700_000_000,
+        }
+    }
+
+    fn spool_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("judge-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("failed")).unwrap();
+        dir
+    }
+
+    fn ids(deliveries: &[Delivery]) -> Vec<&str> {
+        let mut ids: Vec<_> = deliveries.iter().map(|d| d.id.as_str()).collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn sign_matches_hmac_sha256() {
+        // echo -n '1700000000.{"submission_id":1}' | openssl dgst -sha256 -hmac secret
+        assert_eq!(
+            sign("secret", "1700000000", r#"{"submission_id":1}"#),
+            "ab2d739c5960007685570e40e3772c9285dd70daa5e74911152bb6aa867472cd"
+        );
+        assert_ne!(
+            sign("secret", "1700000001", r#"{"submission_id":1}"#),
+            sign("secret", "1700000000", r#"{"submission_id":1}"#)
+        );
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let webhooks = WebhooksConfig {
+            backoff_base_secs: 5,
+            backoff_max_secs: 60,
+            ..Default::default()
+        };
+        let secs: Vec<_> = (1..=6).map(|n| webhooks.backoff(n).as_secs()).collect();
+        assert_eq!(secs, [5, 10, 20, 40, 60, 60]);
+        assert_eq!(webhooks.backoff(0).as_secs(), 5);
+        assert_eq!(webhooks.backoff(u32::MAX).as_secs(), 60);
+    }
+
+    #[tokio::test]
+    async fn spool_round_trip_drops_stale_targets() {
+        let dir = spool_dir("webhook-spool");
+        save(&dir, &delivery("a", "lms")).await;
+        save(&dir, &delivery("b", "removed")).await;
+        save(&dir, &delivery("c", "lms")).await;
+        std::fs::write(dir.join("broken.json"), "{").unwrap();
+
+        let loaded = load_spool(&dir).await;
+        assert_eq!(ids(&loaded), ["a", "b", "c"]);
+        let a = loaded.iter().find(|d| d.id == "a").unwrap();
+        assert_eq!(a.body, r#"{"id":"a"}"#);
+        assert_eq!(a.attempts, 2);
+        assert_eq!(a.next_attempt_at, 1_700_000_000);
+
+        let resumed = resume_spool(&dir, |name| name == "lms").await;
+        assert_eq!(ids(&resumed), ["a", "c"]);
+        assert!(!dir.join("b.json").exists());
+        assert!(dir.join("failed").join("b.json").exists());
+
+        let a = resumed.iter().find(|d| d.id == "a").unwrap();
+        move_to_failed(&dir, a).await;
+        assert_eq!(ids(&load_spool(&dir).await), ["c"]);
+        assert_eq!(ids(&load_spool(&dir.join("failed")).await), ["a", "b"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,38 @@
+//! `scenarios/`의 시뮬레이션 시나리오를 모두 돌림
+//!
+//! 설정은 프로세스마다 한 번만 정할 수 있으므로 시나리오마다 `judge simulate`를 따로 실행함
+
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn scenarios() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenarios");
+    let mut paths: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "no scenarios in {}", dir.display());
+
+    let mut failed = vec![];
+    for path in &paths {
+        let output = Command::new(env!("CARGO_BIN_EXE_judge"))
+            .arg("simulate")
+            .arg(path)
+            .output()
+            .unwrap();
+        if !output.status.success() {
+            eprintln!(
+                "{}:\n{}{}",
+                path.display(),
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+            failed.push(path.display().to_string());
+        }
+    }
+
+    assert!(failed.is_empty(), "failed scenarios: {:?}", failed);
+}